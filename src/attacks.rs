@@ -0,0 +1,71 @@
+use crate::Bitboard;
+use crate::bitboards;
+use crate::CompositeOccupancy;
+use crate::layout;
+use crate::Piece;
+use crate::PieceColor;
+use crate::PieceKind;
+use crate::Position;
+use crate::select_color;
+use crate::select_occupied;
+use crate::locate::BoardLayout;
+use crate::locate::RankwiseSquareOrdinal;
+use crate::move_patterns::instantiate_pattern;
+use crate::move_patterns::KING_PATTERN;
+use crate::move_patterns::KNIGHT_PATTERN;
+use crate::move_patterns::lookup_pawn_capture_pattern;
+use crate::movegen::bishop_reach;
+use crate::movegen::queen_reach;
+use crate::movegen::rook_reach;
+
+/// Computes a [Bitboard] marking the squares which the given `piece`, standing at `origin`,
+/// attacks. Sliding pieces are obstructed by the occupants of `board`. Unlike its moves, the
+/// attacks of a piece include squares occupied by its own color, and exclude a pawn's advances.
+pub(crate) fn piece_attacks(piece: Piece, origin: RankwiseSquareOrdinal, board: &CompositeOccupancy) -> Bitboard {
+    return match piece.kind {
+        PieceKind::Rook => rook_reach(origin, board),
+        PieceKind::Knight => instantiate_pattern(origin, &KNIGHT_PATTERN),
+        PieceKind::Bishop => bishop_reach(origin, board),
+        PieceKind::Queen => queen_reach(origin, board),
+        PieceKind::King => instantiate_pattern(origin, &KING_PATTERN),
+        PieceKind::Pawn => instantiate_pattern(origin, lookup_pawn_capture_pattern(piece.color))
+    }
+}
+
+/// Computes a [Bitboard] marking every square attacked by at least one piece of the given `color`.
+pub(crate) fn attacks_by(position: &Position, color: PieceColor) -> Bitboard {
+    let mut attacks: Bitboard = 0;
+    bitboards::scan(select_color(layout(&position.occupancy, BoardLayout::Rankwise), color), |origin| {
+        let piece = position.squares[origin as usize].occupant.unwrap();
+        attacks |= piece_attacks(piece, origin as usize, &position.occupancy);
+    });
+    return attacks;
+}
+
+/// Computes a [Bitboard] marking the pieces of either color which attack the `target` square.
+/// Only pieces standing on squares which are occupied in `board` are considered, and sliding
+/// pieces are obstructed by the occupants of `board`. So, a piece can be disregarded by first
+/// clearing its square from a copy of the position's [CompositeOccupancy].
+pub(crate) fn attackers_to(position: &Position, target: RankwiseSquareOrdinal, board: &CompositeOccupancy) -> Bitboard {
+    let rw_board = layout(board, BoardLayout::Rankwise);
+    let kinds = &position.kinds;
+    let straight_sliders = kinds[PieceKind::Rook as usize] | kinds[PieceKind::Queen as usize];
+    let diagonal_sliders = kinds[PieceKind::Bishop as usize] | kinds[PieceKind::Queen as usize];
+    let pawns = kinds[PieceKind::Pawn as usize];
+
+    // A pawn attacks the target if a pawn of the opposite color, standing on the target,
+    // would attack the pawn.
+    let white_pawns = pawns & select_color(rw_board, PieceColor::White)
+        & instantiate_pattern(target, lookup_pawn_capture_pattern(PieceColor::Black));
+    let black_pawns = pawns & select_color(rw_board, PieceColor::Black)
+        & instantiate_pattern(target, lookup_pawn_capture_pattern(PieceColor::White));
+
+    let attackers = (instantiate_pattern(target, &KNIGHT_PATTERN) & kinds[PieceKind::Knight as usize])
+        | (instantiate_pattern(target, &KING_PATTERN) & kinds[PieceKind::King as usize])
+        | (rook_reach(target, board) & straight_sliders)
+        | (bishop_reach(target, board) & diagonal_sliders)
+        | white_pawns
+        | black_pawns;
+
+    return attackers & select_occupied(rw_board);
+}
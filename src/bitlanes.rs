@@ -39,7 +39,8 @@ pub(crate) fn slice_d(dordinal: usize, bitboard: Bitboard) -> Bitlane {
     if dordinal > 14 { panic!("Expected dordinal in rage 0 <= dordinal <= 14."); }
     let base = partial_diamond_figurate(8, dordinal);
     let lane = slice(base, bitboard);
-    let length = u8::try_from(measure_diagonal(8, dordinal)).unwrap();
+    // Diagonals are counted from one by measure_diagonal.
+    let length = u8::try_from(measure_diagonal(8, dordinal + 1)).unwrap();
     let trimmed = trim_to(lane, length);
     return trimmed;
 }
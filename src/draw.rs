@@ -0,0 +1,76 @@
+use crate::PieceKind;
+use crate::Position;
+
+/// Why a game is drawn, aside from stalemate.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum DrawReason {
+    /// The same position has occurred three times, with the same color to move.
+    Repetition,
+
+    /// Fifty moves have passed, by each color, without a capture or pawn advance.
+    FiftyMoves,
+
+    /// Neither color has the material to deliver checkmate.
+    InsufficientMaterial
+}
+
+/// The squares of the same color as a1.
+const DARK_SQUARES: u64 = 0xAA55AA55AA55AA55;
+
+/// Counts the earlier occurrences of the current position, with the same color to move. Only the
+/// positions since the last capture, pawn advance or null move are considered, since those moves are
+/// irreversible.
+pub(crate) fn count_repetitions(position: &Position) -> usize {
+    let reversible = (position.halfmove_clock as usize).min(position.history.len());
+    return position.history.iter().rev().take(reversible)
+        .skip(1).step_by(2)
+        .filter(|&&key| key == position.key)
+        .count();
+}
+
+/// Determines whether the fifty-move rule applies. That is, a hundred halfmoves have passed since
+/// the last capture or pawn advance.
+pub(crate) fn is_fifty_move_draw(position: &Position) -> bool {
+    return position.halfmove_clock >= 100;
+}
+
+/// Determines whether neither color can possibly deliver checkmate. That is, only the Kings remain,
+/// besides at most one minor piece, or besides any number of Bishops all on squares of one color.
+pub(crate) fn is_insufficient_material(position: &Position) -> bool {
+    let kinds = &position.kinds;
+    if kinds[PieceKind::Pawn as usize] | kinds[PieceKind::Rook as usize] | kinds[PieceKind::Queen as usize] > 0 {
+        return false;
+    }
+    let knights = kinds[PieceKind::Knight as usize];
+    let bishops = kinds[PieceKind::Bishop as usize];
+    if (knights | bishops).count_ones() <= 1 { return true; }
+    return knights == 0 && (bishops & DARK_SQUARES == 0 || bishops & !DARK_SQUARES == 0);
+}
+
+/// Determines whether the game is drawn by rule, and why, regardless of whether the color to move
+/// has any legal moves. A position repeated `repetitions` times counts as a repetition draw.
+/// Games use threefold repetition, but a search may treat a single repetition as a draw, since
+/// whatever was played to avoid a draw the first time could be played again.
+pub(crate) fn lookup_draw(position: &Position, repetitions: usize) -> Option<DrawReason> {
+    if is_fifty_move_draw(position) { return Some(DrawReason::FiftyMoves); }
+    if is_insufficient_material(position) { return Some(DrawReason::InsufficientMaterial); }
+    if count_repetitions(position) + 1 >= repetitions { return Some(DrawReason::Repetition); }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Position;
+    use crate::draw::count_repetitions;
+    use crate::fen::STARTING_FEN;
+    use crate::makemove::make_null_move;
+
+    #[test]
+    fn null_moves_end_the_reversible_window() {
+        let mut position = Position::from_fen(STARTING_FEN).unwrap();
+        make_null_move(&mut position);
+        make_null_move(&mut position);
+        assert_eq!(position.key, Position::from_fen(STARTING_FEN).unwrap().key);
+        assert_eq!(count_repetitions(&position), 0);
+    }
+}
@@ -0,0 +1,500 @@
+use std::fmt::Write;
+use std::mem::variant_count;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Mul;
+use std::ops::Neg;
+use std::ops::Sub;
+use crate::Bitboard;
+use crate::bitboards;
+use crate::find_king;
+use crate::layout;
+use crate::opponent;
+use crate::PieceColor;
+use crate::PieceKind;
+use crate::Position;
+use crate::select_color;
+use crate::select_pieces;
+use crate::attacks::piece_attacks;
+use crate::locate::BoardLayout;
+use crate::locate::RankwiseSquareOrdinal;
+use crate::locate::split_rwc;
+use crate::move_patterns::instantiate_pattern;
+use crate::move_patterns::KING_PATTERN;
+use crate::search::Score;
+
+/// The nominal value of each [PieceKind], in centipawns, indexed by [PieceKind] ordinal. These are
+/// used where a single, phase-independent value is wanted, such as in move ordering and exchange
+/// evaluation. The King is priceless, and so is valued at zero.
+pub(crate) const PIECE_VALUES: [Score; 6] = [500, 320, 330, 900, 0, 100];
+
+/// A pair of scores, one applying to the middlegame and the other to the endgame, which are
+/// blended according to the phase of the game. See [taper].
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub(crate) struct TaperedScore { pub(crate) mg: Score, pub(crate) eg: Score }
+
+const fn s(mg: Score, eg: Score) -> TaperedScore { return TaperedScore { mg, eg }; }
+
+impl Add for TaperedScore {
+    type Output = TaperedScore;
+    fn add(self, rhs: TaperedScore) -> TaperedScore { return s(self.mg + rhs.mg, self.eg + rhs.eg); }
+}
+
+impl AddAssign for TaperedScore {
+    fn add_assign(&mut self, rhs: TaperedScore) { *self = *self + rhs; }
+}
+
+impl Sub for TaperedScore {
+    type Output = TaperedScore;
+    fn sub(self, rhs: TaperedScore) -> TaperedScore { return s(self.mg - rhs.mg, self.eg - rhs.eg); }
+}
+
+impl Neg for TaperedScore {
+    type Output = TaperedScore;
+    fn neg(self) -> TaperedScore { return s(-self.mg, -self.eg); }
+}
+
+impl Mul<Score> for TaperedScore {
+    type Output = TaperedScore;
+    fn mul(self, rhs: Score) -> TaperedScore { return s(self.mg * rhs, self.eg * rhs); }
+}
+
+/// The phase of a game with all of its minor and major pieces on the board. The phase falls towards
+/// zero as pieces are traded, and the evaluation shifts from middlegame to endgame scores.
+const MAX_PHASE: Score = 24;
+
+/// The contribution of each [PieceKind] to the phase, indexed by [PieceKind] ordinal.
+const PHASE_WEIGHTS: [Score; 6] = [2, 1, 1, 4, 0, 0];
+
+/// Blends the middlegame and endgame components of a [TaperedScore] by the given phase.
+fn taper(score: TaperedScore, phase: Score) -> Score {
+    return (score.mg * phase + score.eg * (MAX_PHASE - phase)) / MAX_PHASE;
+}
+
+/// The tunable parameters of the evaluation. Every term is credited to a color, so penalties are
+/// negative. Arrays indexed by kind are indexed by [PieceKind] ordinal.
+pub(crate) struct Weights {
+    pub(crate) material: [TaperedScore; 6],
+
+    /// Piece-square tables, from White's point of view, as the board is drawn: rank eight first.
+    pub(crate) psqt: [[TaperedScore; 64]; 6],
+
+    /// The value of each square a piece attacks, beyond its [MOBILITY_BASELINE], which is neither
+    /// occupied by its own color nor attacked by an opposing pawn.
+    pub(crate) mobility: [TaperedScore; 6],
+
+    /// Applies once for every pawn beyond the first on a file.
+    pub(crate) doubled_pawn: TaperedScore,
+    pub(crate) isolated_pawn: TaperedScore,
+
+    /// The value of a passed pawn, by its rank, counted from its own side of the board.
+    pub(crate) passed_pawn: [TaperedScore; 8],
+
+    /// The value of a pawn sheltering its King from one and two ranks ahead, respectively.
+    pub(crate) pawn_shelter: [TaperedScore; 2],
+
+    /// The value, to the attacker, of each square next to the opposing King which a piece of the
+    /// given kind attacks.
+    pub(crate) king_attack: [TaperedScore; 6],
+
+    pub(crate) bishop_pair: TaperedScore
+}
+
+/// The number of squares a piece of each kind typically attacks, indexed by [PieceKind] ordinal.
+const MOBILITY_BASELINE: [Score; 6] = [7, 4, 6, 13, 0, 0];
+
+const MG_PSQT: [[Score; 64]; 6] = [
+    // Rook
+    [  0,   0,   0,   0,   0,   0,   0,   0,
+       5,  10,  10,  10,  10,  10,  10,   5,
+      -5,   0,   0,   0,   0,   0,   0,  -5,
+      -5,   0,   0,   0,   0,   0,   0,  -5,
+      -5,   0,   0,   0,   0,   0,   0,  -5,
+      -5,   0,   0,   0,   0,   0,   0,  -5,
+      -5,   0,   0,   0,   0,   0,   0,  -5,
+       0,   0,   0,   5,   5,   0,   0,   0],
+    // Knight
+    [-50, -40, -30, -30, -30, -30, -40, -50,
+     -40, -20,   0,   0,   0,   0, -20, -40,
+     -30,   0,  10,  15,  15,  10,   0, -30,
+     -30,   5,  15,  20,  20,  15,   5, -30,
+     -30,   0,  15,  20,  20,  15,   0, -30,
+     -30,   5,  10,  15,  15,  10,   5, -30,
+     -40, -20,   0,   5,   5,   0, -20, -40,
+     -50, -40, -30, -30, -30, -30, -40, -50],
+    // Bishop
+    [-20, -10, -10, -10, -10, -10, -10, -20,
+     -10,   0,   0,   0,   0,   0,   0, -10,
+     -10,   0,   5,  10,  10,   5,   0, -10,
+     -10,   5,   5,  10,  10,   5,   5, -10,
+     -10,   0,  10,  10,  10,  10,   0, -10,
+     -10,  10,  10,  10,  10,  10,  10, -10,
+     -10,   5,   0,   0,   0,   0,   5, -10,
+     -20, -10, -10, -10, -10, -10, -10, -20],
+    // Queen
+    [-20, -10, -10,  -5,  -5, -10, -10, -20,
+     -10,   0,   0,   0,   0,   0,   0, -10,
+     -10,   0,   5,   5,   5,   5,   0, -10,
+      -5,   0,   5,   5,   5,   5,   0,  -5,
+       0,   0,   5,   5,   5,   5,   0,  -5,
+     -10,   5,   5,   5,   5,   5,   0, -10,
+     -10,   0,   5,   0,   0,   0,   0, -10,
+     -20, -10, -10,  -5,  -5, -10, -10, -20],
+    // King
+    [-30, -40, -40, -50, -50, -40, -40, -30,
+     -30, -40, -40, -50, -50, -40, -40, -30,
+     -30, -40, -40, -50, -50, -40, -40, -30,
+     -30, -40, -40, -50, -50, -40, -40, -30,
+     -20, -30, -30, -40, -40, -30, -30, -20,
+     -10, -20, -20, -20, -20, -20, -20, -10,
+      20,  20,   0,   0,   0,   0,  20,  20,
+      20,  30,  10,   0,   0,  10,  30,  20],
+    // Pawn
+    [  0,   0,   0,   0,   0,   0,   0,   0,
+      50,  50,  50,  50,  50,  50,  50,  50,
+      10,  10,  20,  30,  30,  20,  10,  10,
+       5,   5,  10,  25,  25,  10,   5,   5,
+       0,   0,   0,  20,  20,   0,   0,   0,
+       5,  -5, -10,   0,   0, -10,  -5,   5,
+       5,  10,  10, -20, -20,  10,  10,   5,
+       0,   0,   0,   0,   0,   0,   0,   0]
+];
+
+const EG_PSQT: [[Score; 64]; 6] = [
+    // Rook
+    [  5,   5,   5,   5,   5,   5,   5,   5,
+      10,  10,  10,  10,  10,  10,  10,  10,
+       0,   0,   0,   0,   0,   0,   0,   0,
+       0,   0,   0,   0,   0,   0,   0,   0,
+       0,   0,   0,   0,   0,   0,   0,   0,
+       0,   0,   0,   0,   0,   0,   0,   0,
+       0,   0,   0,   0,   0,   0,   0,   0,
+      -5,  -5,   0,   0,   0,   0,  -5,  -5],
+    // Knight
+    [-50, -40, -30, -30, -30, -30, -40, -50,
+     -40, -20,   0,   0,   0,   0, -20, -40,
+     -30,   0,  10,  15,  15,  10,   0, -30,
+     -30,   5,  15,  20,  20,  15,   5, -30,
+     -30,   0,  15,  20,  20,  15,   0, -30,
+     -30,   5,  10,  15,  15,  10,   5, -30,
+     -40, -20,   0,   5,   5,   0, -20, -40,
+     -50, -40, -30, -30, -30, -30, -40, -50],
+    // Bishop
+    [-20, -10, -10, -10, -10, -10, -10, -20,
+     -10,   0,   0,   0,   0,   0,   0, -10,
+     -10,   0,  10,  10,  10,  10,   0, -10,
+     -10,   0,  10,  15,  15,  10,   0, -10,
+     -10,   0,  10,  15,  15,  10,   0, -10,
+     -10,   0,  10,  10,  10,  10,   0, -10,
+     -10,   0,   0,   0,   0,   0,   0, -10,
+     -20, -10, -10, -10, -10, -10, -10, -20],
+    // Queen
+    [-20, -10, -10,  -5,  -5, -10, -10, -20,
+     -10,   0,   5,   5,   5,   5,   0, -10,
+     -10,   5,  10,  10,  10,  10,   5, -10,
+      -5,   5,  10,  15,  15,  10,   5,  -5,
+      -5,   5,  10,  15,  15,  10,   5,  -5,
+     -10,   5,  10,  10,  10,  10,   5, -10,
+     -10,   0,   5,   5,   5,   5,   0, -10,
+     -20, -10, -10,  -5,  -5, -10, -10, -20],
+    // King
+    [-50, -40, -30, -20, -20, -30, -40, -50,
+     -30, -20, -10,   0,   0, -10, -20, -30,
+     -30, -10,  20,  30,  30,  20, -10, -30,
+     -30, -10,  30,  40,  40,  30, -10, -30,
+     -30, -10,  30,  40,  40,  30, -10, -30,
+     -30, -10,  20,  30,  30,  20, -10, -30,
+     -30, -30,   0,   0,   0,   0, -30, -30,
+     -50, -30, -30, -30, -30, -30, -30, -50],
+    // Pawn
+    [  0,   0,   0,   0,   0,   0,   0,   0,
+      60,  60,  60,  60,  60,  60,  60,  60,
+      40,  40,  40,  40,  40,  40,  40,  40,
+      20,  20,  20,  20,  20,  20,  20,  20,
+      10,  10,  10,  10,  10,  10,  10,  10,
+       5,   5,   5,   5,   5,   5,   5,   5,
+       0,   0,   0,   0,   0,   0,   0,   0,
+       0,   0,   0,   0,   0,   0,   0,   0]
+];
+
+/// Pairs the middlegame and endgame piece-square tables into [TaperedScore]s.
+const fn compile_psqt(mg: &[[Score; 64]; 6], eg: &[[Score; 64]; 6]) -> [[TaperedScore; 64]; 6] {
+    let mut table = [[s(0, 0); 64]; 6];
+    let mut kind = 0;
+    while kind < 6 {
+        let mut i = 0;
+        while i < 64 {
+            table[kind][i] = s(mg[kind][i], eg[kind][i]);
+            i += 1;
+        }
+        kind += 1;
+    }
+    return table;
+}
+
+pub(crate) static WEIGHTS: Weights = Weights {
+    material: [s(500, 520), s(320, 300), s(330, 320), s(900, 930), s(0, 0), s(100, 120)],
+    psqt: compile_psqt(&MG_PSQT, &EG_PSQT),
+    mobility: [s(2, 4), s(4, 4), s(5, 5), s(1, 2), s(0, 0), s(0, 0)],
+    doubled_pawn: s(-10, -20),
+    isolated_pawn: s(-10, -15),
+    passed_pawn: [s(0, 0), s(5, 10), s(5, 15), s(10, 25), s(20, 45), s(35, 75), s(60, 120), s(0, 0)],
+    pawn_shelter: [s(15, 0), s(8, 0)],
+    king_attack: [s(8, 0), s(6, 0), s(6, 0), s(12, 0), s(0, 0), s(4, 0)],
+    bishop_pair: s(30, 50)
+};
+
+/// The components of the evaluation, as reported by [trace].
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Term { Material = 0, PieceSquare = 1, Mobility = 2, PawnStructure = 3, KingSafety = 4, BishopPair = 5 }
+
+const TERMS: [Term; variant_count::<Term>()] = [Term::Material, Term::PieceSquare, Term::Mobility,
+    Term::PawnStructure, Term::KingSafety, Term::BishopPair];
+
+fn term_name(term: Term) -> &'static str {
+    return match term {
+        Term::Material => "Material",
+        Term::PieceSquare => "Piece-square",
+        Term::Mobility => "Mobility",
+        Term::PawnStructure => "Pawn structure",
+        Term::KingSafety => "King safety",
+        Term::BishopPair => "Bishop pair"
+    }
+}
+
+/// The contribution of each [Term] to the evaluation, credited to each color, together with the
+/// phase of the game by which they are blended.
+pub(crate) struct Breakdown {
+    /// Indexed first by [Term] ordinal and then by [PieceColor] ordinal.
+    pub(crate) terms: [[TaperedScore; variant_count::<PieceColor>()]; variant_count::<Term>()],
+    pub(crate) phase: Score
+}
+
+/// Computes the sum of the terms of the given [Breakdown], from White's point of view.
+fn sum(breakdown: &Breakdown) -> TaperedScore {
+    let mut total = TaperedScore::default();
+    for colors in &breakdown.terms {
+        total += colors[PieceColor::White as usize] - colors[PieceColor::Black as usize];
+    }
+    return total;
+}
+
+const FILE_A: Bitboard = 0x0101010101010101;
+
+fn lookup_file(file: usize) -> Bitboard { return FILE_A << file; }
+
+fn lookup_adjacent_files(file: usize) -> Bitboard {
+    let west = if file > 0 { lookup_file(file - 1) } else { 0 };
+    let east = if file < 7 { lookup_file(file + 1) } else { 0 };
+    return west | east;
+}
+
+/// Selects the ranks which lie ahead of the given rank, from the point of view of the given color.
+fn lookup_forward_ranks(rank: usize, color: PieceColor) -> Bitboard {
+    return match color {
+        PieceColor::White if rank == 7 => 0,
+        PieceColor::White => bitboards::OPAQUE << ((rank + 1) * 8),
+        PieceColor::Black => (1 << (rank * 8)) - 1
+    }
+}
+
+/// Counts the ranks from the given color's own side of the board. So, for either color, the
+/// pawns begin on relative rank one.
+fn relative_rank(rank: usize, color: PieceColor) -> usize {
+    return match color {
+        PieceColor::White => rank,
+        PieceColor::Black => 7 - rank
+    }
+}
+
+/// Locates the given square within a piece-square table of the given color. The tables are drawn
+/// from White's point of view, rank eight first, and mirrored for Black.
+fn locate_psqt(sordinal: RankwiseSquareOrdinal, color: PieceColor) -> usize {
+    let (rank, file) = split_rwc(sordinal);
+    return (7 - relative_rank(rank, color)) * 8 + file;
+}
+
+/// Credits each color with the doubled, isolated and passed pawn terms of its own pawns.
+fn evaluate_pawns(position: &Position, weights: &Weights, color: PieceColor) -> TaperedScore {
+    let pawns = select_pieces(position, color, PieceKind::Pawn);
+    let enemy_pawns = select_pieces(position, opponent(color), PieceKind::Pawn);
+    let mut score = TaperedScore::default();
+
+    for file in 0..8 {
+        let count = (pawns & lookup_file(file)).count_ones() as Score;
+        if count > 1 { score += weights.doubled_pawn * (count - 1); }
+    }
+
+    bitboards::scan(pawns, |origin| {
+        let (rank, file) = split_rwc(origin as usize);
+        if pawns & lookup_adjacent_files(file) == 0 { score += weights.isolated_pawn; }
+
+        // A passed pawn meets neither an opposing pawn ahead, nor one which could capture it as it
+        // advances. Of a column of doubled pawns, only the foremost may be passed.
+        let ahead = lookup_forward_ranks(rank, color);
+        let blocked = (enemy_pawns & ahead & (lookup_file(file) | lookup_adjacent_files(file)))
+            | (pawns & ahead & lookup_file(file));
+        if blocked == 0 { score += weights.passed_pawn[relative_rank(rank, color)]; }
+    });
+    return score;
+}
+
+/// Credits the given color for the pawns standing immediately in front of its King.
+fn evaluate_shelter(position: &Position, weights: &Weights, color: PieceColor) -> TaperedScore {
+    let king = find_king(position, color);
+    let (king_rank, king_file) = split_rwc(king);
+    let files = lookup_file(king_file) | lookup_adjacent_files(king_file);
+    let mut score = TaperedScore::default();
+    bitboards::scan(select_pieces(position, color, PieceKind::Pawn) & files, |pawn| {
+        let (pawn_rank, _) = split_rwc(pawn as usize);
+        let distance = relative_rank(pawn_rank, color) as isize - relative_rank(king_rank, color) as isize;
+        if (1..=2).contains(&distance) { score += weights.pawn_shelter[distance as usize - 1]; }
+    });
+    return score;
+}
+
+/// Computes the contribution of each [Term] to the evaluation of the given [Position].
+pub(crate) fn analyze(position: &Position, weights: &Weights) -> Breakdown {
+    let mut breakdown = Breakdown {
+        terms: [[TaperedScore::default(); variant_count::<PieceColor>()]; variant_count::<Term>()],
+        phase: 0
+    };
+    let rw_board = layout(&position.occupancy, BoardLayout::Rankwise);
+
+    // The squares attacked by each color's pawns, and the squares around each color's King.
+    let mut pawn_attacks: [Bitboard; 2] = [0; 2];
+    let mut king_zones: [Bitboard; 2] = [0; 2];
+    for color in [PieceColor::Black, PieceColor::White] {
+        bitboards::scan(select_pieces(position, color, PieceKind::Pawn), |pawn| {
+            let piece = position.squares[pawn as usize].occupant.unwrap();
+            pawn_attacks[color as usize] |= piece_attacks(piece, pawn as usize, &position.occupancy);
+        });
+        let king = find_king(position, color);
+        king_zones[color as usize] = instantiate_pattern(king, &KING_PATTERN) | bitboards::only(king);
+    }
+
+    for color in [PieceColor::Black, PieceColor::White] {
+        let c = color as usize;
+        let enemy = opponent(color) as usize;
+        let own = select_color(rw_board, color);
+        bitboards::scan(own, |origin| {
+            let origin = origin as usize;
+            let piece = position.squares[origin].occupant.unwrap();
+            let k = piece.kind as usize;
+            breakdown.phase += PHASE_WEIGHTS[k];
+            breakdown.terms[Term::Material as usize][c] += weights.material[k];
+            breakdown.terms[Term::PieceSquare as usize][c] += weights.psqt[k][locate_psqt(origin, color)];
+
+            let attacks = piece_attacks(piece, origin, &position.occupancy);
+            breakdown.terms[Term::KingSafety as usize][c] +=
+                weights.king_attack[k] * (attacks & king_zones[enemy]).count_ones() as Score;
+            if piece.kind == PieceKind::Pawn || piece.kind == PieceKind::King { return; }
+            let mobility = (attacks & !own & !pawn_attacks[enemy]).count_ones() as Score;
+            breakdown.terms[Term::Mobility as usize][c] += weights.mobility[k] * (mobility - MOBILITY_BASELINE[k]);
+        });
+
+        breakdown.terms[Term::PawnStructure as usize][c] += evaluate_pawns(position, weights, color);
+        breakdown.terms[Term::KingSafety as usize][c] += evaluate_shelter(position, weights, color);
+        if select_pieces(position, color, PieceKind::Bishop).count_ones() >= 2 {
+            breakdown.terms[Term::BishopPair as usize][c] += weights.bishop_pair;
+        }
+    }
+    breakdown.phase = breakdown.phase.min(MAX_PHASE);
+    return breakdown;
+}
+
+/// Estimates the value of the given [Position] to the color to move, in centipawns.
+pub(crate) fn evaluate(position: &Position) -> Score {
+    let breakdown = analyze(position, &WEIGHTS);
+    let score = taper(sum(&breakdown), breakdown.phase);
+    return match position.ctm {
+        PieceColor::White => score,
+        PieceColor::Black => -score
+    }
+}
+
+/// Tabulates the contribution of each [Term] to the evaluation of the given [Position], for each
+/// color, in centipawns. The totals are given from White's point of view.
+pub(crate) fn trace(position: &Position) -> String {
+    let breakdown = analyze(position, &WEIGHTS);
+    let mut table = String::new();
+    let rule = "---------------+-------------+-------------+-------------";
+    writeln!(table, "          Term |    White    |    Black    |    Total").unwrap();
+    writeln!(table, "               |   MG    EG  |   MG    EG  |   MG    EG").unwrap();
+    writeln!(table, "{}", rule).unwrap();
+    for term in TERMS {
+        let white = breakdown.terms[term as usize][PieceColor::White as usize];
+        let black = breakdown.terms[term as usize][PieceColor::Black as usize];
+        let total = white - black;
+        writeln!(table, "{:>14} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}", term_name(term),
+            white.mg, white.eg, black.mg, black.eg, total.mg, total.eg).unwrap();
+    }
+    writeln!(table, "{}", rule).unwrap();
+    let total = sum(&breakdown);
+    writeln!(table, "{:>14} |             |             | {:>5} {:>5}", "Total", total.mg, total.eg).unwrap();
+    writeln!(table).unwrap();
+    writeln!(table, "Phase: {} of {}", breakdown.phase, MAX_PHASE).unwrap();
+    write!(table, "Evaluation: {} (White's point of view)", taper(total, breakdown.phase)).unwrap();
+    return table;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Position;
+    use crate::eval::analyze;
+    use crate::eval::evaluate;
+    use crate::eval::s;
+    use crate::eval::sum;
+    use crate::eval::taper;
+    use crate::eval::MAX_PHASE;
+    use crate::eval::WEIGHTS;
+    use crate::fen::STARTING_FEN;
+
+    /// Reflects the given FEN record across the middle of the board, exchanging the colors.
+    fn mirror(record: &str) -> String {
+        let fields: Vec<&str> = record.split(' ').collect();
+        let swap = |c: char| if c.is_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() };
+        let ranks: Vec<String> = fields[0].split('/').rev().map(|rank| rank.chars().map(swap).collect()).collect();
+        let ctm = if fields[1] == "w" { "b" } else { "w" };
+        let mut castling: Vec<char> = fields[2].chars().map(swap).collect();
+        castling.sort_by_key(|&c| "KQkq".find(c));
+        let castling: String = castling.into_iter().collect();
+        let ep_target = match fields[3].as_bytes() {
+            [file, rank] => format!("{}{}", *file as char, (b'9' - rank + b'0') as char),
+            _ => String::from("-")
+        };
+        return format!("{} {} {} {} {} {}", ranks.join("/"), ctm, castling, ep_target, fields[4], fields[5]);
+    }
+
+    #[test]
+    fn mirror_symmetry() {
+        let records = [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"
+        ];
+        for record in records {
+            let position = Position::from_fen(record).unwrap();
+            let mirrored = Position::from_fen(&mirror(record)).unwrap();
+            assert_eq!(evaluate(&position), evaluate(&mirrored), "{}", record);
+        }
+    }
+
+    #[test]
+    fn phase_extremes() {
+        assert_eq!(taper(s(100, -40), MAX_PHASE), 100);
+        assert_eq!(taper(s(100, -40), 0), -40);
+
+        let opening = Position::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4").unwrap();
+        let breakdown = analyze(&opening, &WEIGHTS);
+        assert_eq!(breakdown.phase, MAX_PHASE);
+        assert_eq!(evaluate(&opening), sum(&breakdown).mg);
+
+        let ending = Position::from_fen("4k3/pp6/8/8/8/8/5PPP/4K3 b - - 0 1").unwrap();
+        let breakdown = analyze(&ending, &WEIGHTS);
+        assert_eq!(breakdown.phase, 0);
+        assert_eq!(evaluate(&ending), -sum(&breakdown).eg);
+    }
+}
@@ -0,0 +1,411 @@
+use std::fmt;
+use crate::bitboards;
+use crate::castling_right;
+use crate::CastlingRights;
+use crate::CastlingSide;
+use crate::find_king;
+use crate::NO_CASTLING_RIGHTS;
+use crate::opponent;
+use crate::Piece;
+use crate::PieceColor;
+use crate::PieceKind;
+use crate::place_piece;
+use crate::Position;
+use crate::select_pieces;
+use crate::attacks::attacks_by;
+use crate::locate::format_algebraic;
+use crate::locate::parse_algebraic;
+use crate::locate::File;
+use crate::locate::Rank;
+use crate::movegen::is_en_passant_threatened;
+use crate::zobrist::compute_key;
+
+/// The [Forsyth–Edwards Notation](https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation)
+/// record of the standard starting position.
+pub(crate) const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Names one of the six space-separated fields of a FEN record.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum FenField {
+    Placement = 0,
+    ActiveColor = 1,
+    CastlingRights = 2,
+    EnPassantTarget = 3,
+    HalfmoveClock = 4,
+    FullmoveNumber = 5
+}
+
+const FIELDS: [FenField; 6] = [FenField::Placement, FenField::ActiveColor, FenField::CastlingRights,
+    FenField::EnPassantTarget, FenField::HalfmoveClock, FenField::FullmoveNumber];
+
+/// Describes why a FEN record could not be parsed. Every variant carries the [FenField] which
+/// failed, and the byte offset into the record at which the failure was detected.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum FenError {
+    /// The record ended before the given mandatory field. The offset is the length of the record.
+    MissingField { field: FenField, offset: usize },
+
+    /// The given character is not permitted at this point in the field.
+    UnexpectedChar { field: FenField, offset: usize, found: char },
+
+    /// A rank of the placement field describes more or fewer than eight squares.
+    /// The offset is that of the first character of the rank.
+    BadRankLength { field: FenField, offset: usize },
+
+    /// The placement field describes more or fewer than eight ranks.
+    BadRankCount { field: FenField, offset: usize },
+
+    /// The field does not name a square on which an en-passant capture could occur.
+    BadSquare { field: FenField, offset: usize },
+
+    /// The field is not a non-negative integer which fits in the clock.
+    BadNumber { field: FenField, offset: usize },
+
+    /// The placement field does not give the given color exactly one King.
+    /// The offset is that of the placement field.
+    BadKingCount { field: FenField, offset: usize, color: PieceColor },
+
+    /// The placement field puts a pawn on the first or eighth rank. The offset is that of the pawn.
+    PawnOnBackRank { field: FenField, offset: usize },
+
+    /// The King of the color not to move is in check, so the color to move could capture it.
+    /// The offset is that of the active color field.
+    OpponentInCheck { field: FenField, offset: usize },
+
+    /// The record continues after the final field.
+    TrailingInput { field: FenField, offset: usize }
+}
+
+impl FenError {
+    pub(crate) fn field(self) -> FenField {
+        return match self {
+            FenError::MissingField { field, .. } => field,
+            FenError::UnexpectedChar { field, .. } => field,
+            FenError::BadRankLength { field, .. } => field,
+            FenError::BadRankCount { field, .. } => field,
+            FenError::BadSquare { field, .. } => field,
+            FenError::BadNumber { field, .. } => field,
+            FenError::BadKingCount { field, .. } => field,
+            FenError::PawnOnBackRank { field, .. } => field,
+            FenError::OpponentInCheck { field, .. } => field,
+            FenError::TrailingInput { field, .. } => field,
+        }
+    }
+
+    pub(crate) fn offset(self) -> usize {
+        return match self {
+            FenError::MissingField { offset, .. } => offset,
+            FenError::UnexpectedChar { offset, .. } => offset,
+            FenError::BadRankLength { offset, .. } => offset,
+            FenError::BadRankCount { offset, .. } => offset,
+            FenError::BadSquare { offset, .. } => offset,
+            FenError::BadNumber { offset, .. } => offset,
+            FenError::BadKingCount { offset, .. } => offset,
+            FenError::PawnOnBackRank { offset, .. } => offset,
+            FenError::OpponentInCheck { offset, .. } => offset,
+            FenError::TrailingInput { offset, .. } => offset,
+        }
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self {
+            FenError::MissingField { .. } => String::from("field is missing"),
+            FenError::UnexpectedChar { found, .. } => format!("unexpected character '{}'", found),
+            FenError::BadRankLength { .. } => String::from("rank does not describe eight squares"),
+            FenError::BadRankCount { .. } => String::from("placement does not describe eight ranks"),
+            FenError::BadSquare { .. } => String::from("not a valid en-passant square"),
+            FenError::BadNumber { .. } => String::from("not a valid number"),
+            FenError::BadKingCount { color, .. } => format!("{:?} does not have exactly one King", color),
+            FenError::PawnOnBackRank { .. } => String::from("pawn on the first or eighth rank"),
+            FenError::OpponentInCheck { .. } => String::from("the color not to move is in check"),
+            FenError::TrailingInput { .. } => String::from("unexpected input after the final field")
+        };
+        return write!(f, "{:?} field at offset {}: {}", self.field(), self.offset(), problem);
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// Splits the given record into its whitespace-separated fields, pairing each field with the byte
+/// offset at which it begins.
+fn split_fields(record: &str) -> Vec<(usize, &str)> {
+    let mut fields: Vec<(usize, &str)> = Vec::new();
+    let mut begin: Option<usize> = None;
+    for (offset, c) in record.char_indices() {
+        match (c.is_whitespace(), begin) {
+            (true, Some(b)) => { Vec::push(&mut fields, (b, &record[b..offset])); begin = None; }
+            (false, None) => { begin = Some(offset); }
+            _ => {}
+        }
+    }
+    if let Some(b) = begin { Vec::push(&mut fields, (b, &record[b..])); }
+    return fields;
+}
+
+fn parse_piece(c: char) -> Option<Piece> {
+    let color = if c.is_ascii_uppercase() { PieceColor::White } else { PieceColor::Black };
+    let kind = match c.to_ascii_lowercase() {
+        'r' => PieceKind::Rook,
+        'n' => PieceKind::Knight,
+        'b' => PieceKind::Bishop,
+        'q' => PieceKind::Queen,
+        'k' => PieceKind::King,
+        'p' => PieceKind::Pawn,
+        _ => return None
+    };
+    return Some(Piece { color, kind });
+}
+
+fn format_piece(piece: Piece) -> char {
+    const SYMBOLS: [char; 6] = ['r', 'n', 'b', 'q', 'k', 'p'];
+    let symbol = SYMBOLS[piece.kind as usize];
+    return match piece.color {
+        PieceColor::Black => symbol,
+        PieceColor::White => symbol.to_ascii_uppercase()
+    }
+}
+
+fn parse_placement(position: &mut Position, base: usize, field: &str) -> Result<(), FenError> {
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::BadRankCount { field: FenField::Placement, offset: base });
+    }
+
+    let mut rank_offset = base;
+    for (i, rank_text) in ranks.iter().enumerate() {
+        let rank: Rank = 7 - i;
+        let mut file: File = 0;
+        for (j, c) in rank_text.char_indices() {
+            let offset = rank_offset + j;
+            if file >= 8 { return Err(FenError::BadRankLength { field: FenField::Placement, offset: rank_offset }); }
+            match c {
+                '1'..='8' => { file += c as usize - '0' as usize; }
+                _ => {
+                    let piece = parse_piece(c)
+                        .ok_or(FenError::UnexpectedChar { field: FenField::Placement, offset, found: c })?;
+                    if piece.kind == PieceKind::Pawn && (rank == 0 || rank == 7) {
+                        return Err(FenError::PawnOnBackRank { field: FenField::Placement, offset });
+                    }
+                    place_piece(position, rank * 8 + file, piece);
+                    file += 1;
+                }
+            }
+        }
+        if file != 8 { return Err(FenError::BadRankLength { field: FenField::Placement, offset: rank_offset }); }
+        rank_offset += rank_text.len() + 1;
+    }
+    for color in [PieceColor::White, PieceColor::Black] {
+        if select_pieces(position, color, PieceKind::King).count_ones() != 1 {
+            return Err(FenError::BadKingCount { field: FenField::Placement, offset: base, color });
+        }
+    }
+    return Ok(());
+}
+
+fn parse_active_color(base: usize, field: &str) -> Result<PieceColor, FenError> {
+    return match field {
+        "w" => Ok(PieceColor::White),
+        "b" => Ok(PieceColor::Black),
+        _ => {
+            // The field is non-empty, and is either an unknown color or has excess characters.
+            let (offset, found) = field.char_indices()
+                .find(|&(offset, c)| offset > 0 || (c != 'w' && c != 'b'))
+                .unwrap();
+            Err(FenError::UnexpectedChar { field: FenField::ActiveColor, offset: base + offset, found })
+        }
+    }
+}
+
+fn parse_castling_rights(base: usize, field: &str) -> Result<CastlingRights, FenError> {
+    if field == "-" { return Ok(NO_CASTLING_RIGHTS); }
+    let mut rights = NO_CASTLING_RIGHTS;
+    for (offset, c) in field.char_indices() {
+        rights |= match c {
+            'K' => castling_right(PieceColor::White, CastlingSide::Kingside),
+            'Q' => castling_right(PieceColor::White, CastlingSide::Queenside),
+            'k' => castling_right(PieceColor::Black, CastlingSide::Kingside),
+            'q' => castling_right(PieceColor::Black, CastlingSide::Queenside),
+            _ => return Err(FenError::UnexpectedChar { field: FenField::CastlingRights, offset: base + offset,
+                found: c })
+        };
+    }
+    return Ok(rights);
+}
+
+/// Parses the en-passant target of the given [Position], whose placement and active color are known.
+/// The target must lie behind a pawn of the color not to move, which has just advanced two squares.
+/// That is, on the sixth rank with White to move, or on the third rank with Black to move. The pawn
+/// has just passed over the target from the square beyond it, so both of those must be vacant.
+///
+/// A target which no pawn of the color to move could capture onto is dropped, as [make_move] would
+/// never have set it, so that the position's key matches that of the same position reached by play.
+///
+/// [make_move]: crate::makemove::make_move
+fn parse_ep_target(position: &Position, base: usize, field: &str) -> Result<Option<usize>, FenError> {
+    if field == "-" { return Ok(None); }
+    let error = FenError::BadSquare { field: FenField::EnPassantTarget, offset: base };
+    let sordinal = parse_algebraic(field).ok_or(error)?;
+    let rank: Rank = sordinal / 8;
+    let (pusher, origin) = match position.ctm {
+        PieceColor::White if rank == 5 => (sordinal - 8, sordinal + 8),
+        PieceColor::Black if rank == 2 => (sordinal + 8, sordinal - 8),
+        _ => return Err(error)
+    };
+    if select_pieces(position, opponent(position.ctm), PieceKind::Pawn) & bitboards::only(pusher) == 0 {
+        return Err(error);
+    }
+    if position.squares[sordinal].occupant.is_some() || position.squares[origin].occupant.is_some() {
+        return Err(error);
+    }
+    if !is_en_passant_threatened(position, pusher, position.ctm) { return Ok(None); }
+    return Ok(Some(sordinal));
+}
+
+fn parse_clock(field_kind: FenField, base: usize, field: &str) -> Result<u32, FenError> {
+    return field.parse::<u32>().map_err(|_| FenError::BadNumber { field: field_kind, offset: base });
+}
+
+impl Position {
+    /// Parses a record in Forsyth–Edwards Notation.
+    ///
+    /// The halfmove clock and fullmove number may be omitted together, as is common in EPD
+    /// test suites, in which case they default to `0` and `1` respectively.
+    pub(crate) fn from_fen(record: &str) -> Result<Position, FenError> {
+        let fields = split_fields(record);
+        let mandatory = if fields.len() == 4 { 4 } else { FIELDS.len() };
+        if fields.len() < mandatory {
+            return Err(FenError::MissingField { field: FIELDS[fields.len()], offset: record.len() });
+        }
+        if fields.len() > FIELDS.len() {
+            let (offset, _) = fields[FIELDS.len()];
+            return Err(FenError::TrailingInput { field: FenField::FullmoveNumber, offset });
+        }
+
+        let mut position = Position::empty();
+        let (base, field) = fields[FenField::Placement as usize];
+        parse_placement(&mut position, base, field)?;
+        let (base, field) = fields[FenField::ActiveColor as usize];
+        position.ctm = parse_active_color(base, field)?;
+        let enemy_king = bitboards::only(find_king(&position, opponent(position.ctm)));
+        if attacks_by(&position, position.ctm) & enemy_king > 0 {
+            return Err(FenError::OpponentInCheck { field: FenField::ActiveColor, offset: base });
+        }
+        let (base, field) = fields[FenField::CastlingRights as usize];
+        position.castling = parse_castling_rights(base, field)?;
+        let (base, field) = fields[FenField::EnPassantTarget as usize];
+        position.ep_target = parse_ep_target(&position, base, field)?;
+        if fields.len() == FIELDS.len() {
+            let (base, field) = fields[FenField::HalfmoveClock as usize];
+            position.halfmove_clock = parse_clock(FenField::HalfmoveClock, base, field)?;
+            let (base, field) = fields[FenField::FullmoveNumber as usize];
+            position.fullmove_number = parse_clock(FenField::FullmoveNumber, base, field)?;
+        }
+        position.key = compute_key(&position);
+        return Ok(position);
+    }
+
+    /// Serializes this [Position] into Forsyth–Edwards Notation. This is the inverse of [Position::from_fen].
+    pub(crate) fn to_fen(&self) -> String {
+        let mut record = String::new();
+
+        for rank in (0..8).rev() {
+            let mut vacant = 0;
+            for file in 0..8 {
+                match self.squares[rank * 8 + file].occupant {
+                    None => { vacant += 1; }
+                    Some(piece) => {
+                        if vacant > 0 { record.push_str(&vacant.to_string()); vacant = 0; }
+                        record.push(format_piece(piece));
+                    }
+                }
+            }
+            if vacant > 0 { record.push_str(&vacant.to_string()); }
+            if rank > 0 { record.push('/'); }
+        }
+
+        record.push(' ');
+        record.push(match self.ctm { PieceColor::White => 'w', PieceColor::Black => 'b' });
+
+        record.push(' ');
+        if self.castling == NO_CASTLING_RIGHTS { record.push('-'); }
+        let symbols = [
+            ('K', castling_right(PieceColor::White, CastlingSide::Kingside)),
+            ('Q', castling_right(PieceColor::White, CastlingSide::Queenside)),
+            ('k', castling_right(PieceColor::Black, CastlingSide::Kingside)),
+            ('q', castling_right(PieceColor::Black, CastlingSide::Queenside))
+        ];
+        for (symbol, right) in symbols {
+            if self.castling & right > 0 { record.push(symbol); }
+        }
+
+        record.push(' ');
+        match self.ep_target {
+            None => record.push('-'),
+            Some(sordinal) => record.push_str(&format_algebraic(sordinal))
+        }
+
+        record.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
+        return record;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PieceColor;
+    use crate::Position;
+    use crate::fen::FenError;
+    use crate::fen::FenField;
+    use crate::fen::STARTING_FEN;
+
+    #[test]
+    fn round_trip() {
+        for record in [STARTING_FEN, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                       "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"] {
+            assert_eq!(Position::from_fen(record).unwrap().to_fen(), record);
+        }
+    }
+
+    #[test]
+    fn rejects_missing_king() {
+        let error = Position::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").err();
+        assert_eq!(error, Some(FenError::BadKingCount { field: FenField::Placement, offset: 0,
+            color: PieceColor::White }));
+        let error = Position::from_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1").err();
+        assert!(matches!(error, Some(FenError::BadKingCount { color: PieceColor::White, .. })));
+    }
+
+    #[test]
+    fn rejects_pawn_on_back_rank() {
+        let error = Position::from_fen("4k2P/8/8/8/8/8/8/4K3 w - - 0 1").err();
+        assert_eq!(error, Some(FenError::PawnOnBackRank { field: FenField::Placement, offset: 3 }));
+    }
+
+    #[test]
+    fn rejects_opponent_in_check() {
+        let error = Position::from_fen("4k3/4R3/8/8/8/8/8/4K3 w - - 0 1").err();
+        assert_eq!(error, Some(FenError::OpponentInCheck { field: FenField::ActiveColor, offset: 22 }));
+        assert!(Position::from_fen("4k3/4R3/8/8/8/8/8/4K3 b - - 0 1").is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_ep_target() {
+        // The target must lie behind a pawn of the color not to move, on the rank that color's pawns skip.
+        assert!(Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").is_ok());
+        assert!(Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1").is_err());
+        assert!(Position::from_fen("4k3/8/8/8/8/8/3P4/4K3 w - e3 0 1").is_err());
+        assert!(Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - e3 0 1").is_err());
+    }
+
+    #[test]
+    fn rejects_occupied_ep_target() {
+        // The pawn cannot have passed over, nor come from, an occupied square.
+        let error = Position::from_fen("4k3/8/4n3/3Pp3/8/8/8/4K3 w - e6 0 1").err();
+        assert_eq!(error, Some(FenError::BadSquare { field: FenField::EnPassantTarget, offset: 29 }));
+        assert!(Position::from_fen("4k3/4n3/8/3Pp3/8/8/8/4K3 w - e6 0 1").is_err());
+        assert!(Position::from_fen("4k3/8/8/8/3pP3/4N3/8/4K3 b - e3 0 1").is_err());
+        assert!(Position::from_fen("4k3/8/8/8/3pP3/8/4N3/4K3 b - e3 0 1").is_err());
+        assert!(Position::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").is_ok());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::Bitboard;
+use crate::bitboards;
+use crate::clear;
+use crate::fill;
+use crate::CompositeOccupancy;
+use crate::find_king;
+use crate::layout;
+use crate::opponent;
+use crate::PieceKind;
+use crate::Position;
+use crate::select_color;
+use crate::select_occupied;
+use crate::select_pieces;
+use crate::Translation;
+use crate::attacks::attackers_to;
+use crate::attacks::attacks_by;
+use crate::locate::BoardLayout;
+use crate::locate::RankwiseSquareOrdinal;
+use crate::movegen::bishop_reach;
+use crate::movegen::generate_captures;
+use crate::movegen::generate_from;
+use crate::movegen::generate_moves;
+use crate::movegen::generate_promotions;
+use crate::movegen::generate_quiets;
+use crate::movegen::locate_en_passant_victim;
+use crate::movegen::MoveList;
+use crate::movegen::rook_reach;
+use crate::rays::lookup_between;
+use crate::rays::lookup_line;
+
+/// Computes a [Bitboard] marking the opponent's pieces which give check to the King of the color to move.
+pub(crate) fn checkers(position: &Position) -> Bitboard {
+    let king = find_king(position, position.ctm);
+    let enemies = select_color(layout(&position.occupancy, BoardLayout::Rankwise), opponent(position.ctm));
+    return attackers_to(position, king, &position.occupancy) & enemies;
+}
+
+pub(crate) fn in_check(position: &Position) -> bool {
+    return checkers(position) > 0;
+}
+
+/// Computes a [Bitboard] marking the pieces of the color to move which are pinned to their own King.
+/// That is, pieces which are the sole obstruction between the King and an opposing sliding piece.
+fn pinned(position: &Position, king: RankwiseSquareOrdinal) -> Bitboard {
+    let enemy = opponent(position.ctm);
+    let rw_board = layout(&position.occupancy, BoardLayout::Rankwise);
+    let queens = select_pieces(position, enemy, PieceKind::Queen);
+
+    // Consider the sliding pieces which would attack the King, were the board empty.
+    let vacant = CompositeOccupancy::default();
+    let snipers = (rook_reach(king, &vacant) & (select_pieces(position, enemy, PieceKind::Rook) | queens))
+        | (bishop_reach(king, &vacant) & (select_pieces(position, enemy, PieceKind::Bishop) | queens));
+
+    let mut pinned: Bitboard = 0;
+    bitboards::scan(snipers, |sniper| {
+        let obstructions = lookup_between(king, sniper as usize) & select_occupied(rw_board);
+        if obstructions.count_ones() == 1 && obstructions & select_color(rw_board, position.ctm) > 0 {
+            pinned |= obstructions;
+        }
+    });
+    return pinned;
+}
+
+/// Pushes each of the given pseudo-legal `candidates` onto `moves`, provided that it does not leave
+/// the mover's own King in check.
+///
+/// While in check by a single piece, the King must move, or another piece must capture the checker
+/// or obstruct its path. While in check by two pieces, only the King may move. A piece which is
+/// pinned to its King may only move along the line between the King and the pinning piece.
+/// The King may castle only while not in check, and only if it neither passes through nor lands
+/// upon an attacked square. An en-passant capture is legal only if the King is not in check once
+/// both pawns have left their squares, which rules out captures exposing the King along the rank.
+fn retain_legal(position: &Position, candidates: MoveList, moves: &mut MoveList) {
+    let enemies = select_color(layout(&position.occupancy, BoardLayout::Rankwise), opponent(position.ctm));
+    let king = find_king(position, position.ctm);
+    let checkers = attackers_to(position, king, &position.occupancy) & enemies;
+    let pinned = pinned(position, king);
+
+    // The squares onto which a piece other than the King must move to resolve check.
+    let evasions: Bitboard = match checkers.count_ones() {
+        0 => bitboards::OPAQUE,
+        1 => checkers | lookup_between(king, checkers.trailing_zeros() as usize),
+        _ => 0
+    };
+
+    // The King must not be able to hide behind itself from a sliding piece.
+    let mut kingless = position.occupancy;
+    clear(&mut kingless, position.ctm, king);
+
+    for translation in candidates {
+        let origin = translation.origin();
+        let destination = translation.destination();
+
+        if translation.is_castle() {
+            if checkers > 0 { continue; }
+            let path = lookup_between(origin, destination) | bitboards::only(destination);
+            if path & attacks_by(position, opponent(position.ctm)) == 0 { Vec::push(moves, translation); }
+            continue;
+        }
+
+        if translation.is_en_passant() {
+            let mut after = position.occupancy;
+            clear(&mut after, position.ctm, origin);
+            clear(&mut after, opponent(position.ctm), locate_en_passant_victim(destination, position.ctm));
+            fill(&mut after, position.ctm, destination);
+            if attackers_to(position, king, &after) & enemies == 0 { Vec::push(moves, translation); }
+            continue;
+        }
+
+        if origin == king {
+            if attackers_to(position, destination, &kingless) & enemies > 0 { continue; }
+            Vec::push(moves, translation);
+            continue;
+        }
+
+        if bitboards::only(destination) & evasions == 0 { continue; }
+        if pinned & bitboards::only(origin) > 0 && lookup_line(king, origin) & bitboards::only(destination) == 0 {
+            continue;
+        }
+        Vec::push(moves, translation);
+    }
+}
+
+/// Generates every legal move available to the color to move. That is, every pseudo-legal move
+/// (see [generate_moves]) which does not leave the mover's own King in check.
+pub(crate) fn generate_legal_moves(position: &Position, moves: &mut MoveList) {
+    let mut candidates = MoveList::new();
+    generate_moves(position, &mut candidates);
+    retain_legal(position, candidates, moves);
+}
+
+/// Generates the legal moves of the color to move which capture a piece or promote a pawn.
+/// See also, [generate_captures] and [generate_promotions].
+pub(crate) fn generate_legal_captures(position: &Position, moves: &mut MoveList) {
+    let mut candidates = MoveList::new();
+    generate_captures(position, &mut candidates);
+    generate_promotions(position, &mut candidates);
+    retain_legal(position, candidates, moves);
+}
+
+/// Generates the legal moves of the color to move which neither capture a piece nor promote a pawn.
+/// These are the moves which [generate_legal_captures] omits.
+pub(crate) fn generate_legal_quiets(position: &Position, moves: &mut MoveList) {
+    let mut candidates = MoveList::new();
+    generate_quiets(position, &mut candidates);
+    candidates.retain(|translation| translation.promotion().is_none());
+    retain_legal(position, candidates, moves);
+}
+
+/// Determines whether the given [Translation] is a legal move for the color to move. This is for
+/// moves which were not generated in the given position, such as those recalled from an earlier
+/// search, and which may describe no move at all.
+pub(crate) fn is_legal(position: &Position, translation: Translation) -> bool {
+    let mut candidates = MoveList::new();
+    generate_from(position, translation.origin(), bitboards::OPAQUE, &mut candidates);
+    candidates.retain(|&candidate| candidate == translation);
+    let mut moves = MoveList::new();
+    retain_legal(position, candidates, &mut moves);
+    return !moves.is_empty();
+}
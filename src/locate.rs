@@ -1,6 +1,7 @@
 use std::cmp::{max, min};
 use crate::Antidiagonal;
 use crate::Diagonal;
+use crate::misc::partial_diamond_figurate;
 
 pub(crate) type Rank = usize;
 pub(crate) type File = usize;
@@ -25,6 +26,11 @@ pub(crate) enum BoardLayout {
     Filewise = 3
 }
 
+impl BoardLayout {
+    pub(crate) const ALL: [BoardLayout; 4] = [BoardLayout::Antidiagonal, BoardLayout::Diagonal,
+        BoardLayout::Rankwise, BoardLayout::Filewise];
+}
+
 
 /// A natural number in the range 0 <= i <= 63 denoting a particular
 /// square's location under [BoardLayout::Rankwise].
@@ -64,10 +70,10 @@ pub(crate) struct DiagonalSquareCoordinate {
 pub(crate) fn locate_ad(sordinal: RankwiseSquareOrdinal) -> DiagonalSquareCoordinate {
     let (rank, file) = split_rwc(sordinal);
     let antidiagonal: Antidiagonal = rank + file;
-    let origin_sordinal = antidiagonal + (8 * max(antidiagonal - 8, 0));
+    let origin_rank: Rank = usize::saturating_sub(antidiagonal, 7);
     return DiagonalSquareCoordinate {
         diagonal: antidiagonal,
-        offset: (sordinal - origin_sordinal) / 8
+        offset: rank - origin_rank
     };
 }
 
@@ -76,20 +82,18 @@ pub(crate) fn locate_ad(sordinal: RankwiseSquareOrdinal) -> DiagonalSquareCoordi
 /// position in terms of the [Diagonal] intersecting the square and the distance (measured in
 /// squares) from the beginning of that [Diagonal] to the square.
 pub(crate) fn locate_d(sordinal: RankwiseSquareOrdinal) -> DiagonalSquareCoordinate {
-    let rank: usize = sordinal / 8;
-    let file: usize = 7 - (sordinal % 8);
-    let diagonal: Diagonal = rank + file;
-    let reflected_sordinal = rank * 8 + file;
-    let origin_sordinal = diagonal + (8 * max((diagonal as isize) - 8isize, 0isize)) as usize;
+    let (rank, file) = split_rwc(sordinal);
+    let diagonal: Diagonal = rank + (7 - file);
+    let origin_rank: Rank = usize::saturating_sub(diagonal, 7);
     return DiagonalSquareCoordinate {
         diagonal,
-        offset: (reflected_sordinal - origin_sordinal) / 8
+        offset: rank - origin_rank
     };
 }
 
 /// Calculates the [RankwiseSquareOrdinal] of a *diagonal square coordinate*.
 pub(crate) fn reverse_locate_d(dordinal: DiagonalSquareCoordinate) -> RankwiseSquareOrdinal {
-    let base_file = (7usize - min(7usize, dordinal.diagonal));
+    let base_file = 7usize - min(7usize, dordinal.diagonal);
     let base_rank = max(0isize, dordinal.diagonal as isize - 7isize) as usize;
     let offset = (base_rank * 8) + (dordinal.offset * 9usize);
     return base_file + offset;
@@ -107,6 +111,8 @@ pub(crate) fn locate_fw(ordinal: RankwiseSquareOrdinal) -> FilewiseSquareOrdinal
     return file * 8 + rank;
 }
 
+/// The inverse of [locate_fw]. Kept alongside it, though nothing converts back yet.
+#[allow(dead_code)]
 pub(crate) fn reverse_locate_fw(ordinal: FilewiseSquareOrdinal) -> RankwiseSquareOrdinal {
     let (rank, file) = split_fwc(ordinal);
     return rank * 8 + file;
@@ -122,4 +128,44 @@ pub(crate) fn split_fwc(sordinal: FilewiseSquareOrdinal) -> (Rank, File) {
     let rank: usize = sordinal % 8;
     let file: usize = sordinal / 8;
     return (rank, file);
-}
\ No newline at end of file
+}
+
+/// Calculates the ordinal of the bit which represents the given square in a [Bitboard](crate::Bitboard)
+/// arranged under the given [BoardLayout].
+///
+/// Under [BoardLayout::Diagonal] and [BoardLayout::Antidiagonal], the diagonals are laid end to end,
+/// beginning with diagonal zero. See also, [slice_d](crate::bitlanes::slice_d).
+pub(crate) fn locate(layout: BoardLayout, sordinal: RankwiseSquareOrdinal) -> usize {
+    return match layout {
+        BoardLayout::Rankwise => sordinal,
+        BoardLayout::Filewise => locate_fw(sordinal),
+        BoardLayout::Diagonal => {
+            let coordinate = locate_d(sordinal);
+            partial_diamond_figurate(8, coordinate.diagonal) + coordinate.offset
+        },
+        BoardLayout::Antidiagonal => {
+            let coordinate = locate_ad(sordinal);
+            partial_diamond_figurate(8, coordinate.diagonal) + coordinate.offset
+        }
+    }
+}
+
+/// Parses the name of a square in algebraic notation, for instance `e4`, into a [RankwiseSquareOrdinal].
+/// Files `a` through `h` correspond to files zero through seven, and ranks `1` through `8` correspond
+/// to ranks zero through seven.
+pub(crate) fn parse_algebraic(name: &str) -> Option<RankwiseSquareOrdinal> {
+    let bytes = name.as_bytes();
+    if bytes.len() != 2 { return None; }
+    let file: File = match bytes[0] { b'a'..=b'h' => usize::from(bytes[0] - b'a'), _ => return None };
+    let rank: Rank = match bytes[1] { b'1'..=b'8' => usize::from(bytes[1] - b'1'), _ => return None };
+    return Some(rank * 8 + file);
+}
+
+/// Produces the name of the given square in algebraic notation. This is the inverse of [parse_algebraic].
+pub(crate) fn format_algebraic(sordinal: RankwiseSquareOrdinal) -> String {
+    let (rank, file) = split_rwc(sordinal);
+    let mut name = String::with_capacity(2);
+    name.push(char::from(b'a' + file as u8));
+    name.push(char::from(b'1' + rank as u8));
+    return name;
+}
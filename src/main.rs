@@ -1,6 +1,7 @@
 #![feature(variant_count)]
-#![feature(exclusive_range_pattern)]
 #![feature(portable_simd)]
+// The codebase favors explicit `return` statements.
+#![allow(clippy::needless_return)]
 
 mod misc;
 mod bitboards;
@@ -9,23 +10,35 @@ mod obstruct;
 mod bitlanes;
 mod movegen;
 mod move_patterns;
+mod fen;
+mod makemove;
+mod rays;
+mod attacks;
+mod legal;
+mod notation;
+mod perft;
+mod uci;
+mod search;
+mod eval;
+mod see;
+mod zobrist;
+mod tt;
+mod movepick;
+mod timeman;
+mod draw;
+mod status;
+mod san;
 
 use std::mem::variant_count;
-use std::ops::Add;
-use std::ops::Mul;
-use std::ops::Shl;
-use std::ops::ShlAssign;
-use std::simd::SimdInt;
-use std::simd::SimdOrd;
-use std::simd::SimdUint;
-use std::simd::SupportedLaneCount;
-use std::u16;
 use locate::BoardLayout;
+use locate::RankwiseSquareOrdinal;
+use zobrist::lookup_piece_key;
+use zobrist::ZobristKey;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 enum PieceKind { Rook = 0, Knight = 1, Bishop = 2, Queen = 3, King = 4, Pawn = 5 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 enum PieceColor { Black = 0, White = 1 }
 
 fn opponent(color: PieceColor) -> PieceColor {
@@ -39,10 +52,13 @@ type Bitlane = u8;
 
 type Bitboard = u64;
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 struct Piece { color: PieceColor, kind: PieceKind }
 
+#[derive(Copy, Clone)]
 struct Square { occupant: Option<Piece> }
 
+#[derive(Copy, Clone, Default)]
 pub(crate) struct Occupancy {
     boards: [Bitboard; variant_count::<PieceColor>()]
 }
@@ -50,6 +66,7 @@ pub(crate) struct Occupancy {
 /// Describes the occupancy of the chessboard (which squares have white pieces, which squares have
 /// black pieces). Specifically, this struct encapsulates multiple [Bitboard], each describing the
 /// occupancy of the chess board in a different [BoardLayout]. See also, [layout].
+#[derive(Copy, Clone, Default)]
 pub(crate) struct CompositeOccupancy {
     boards: [Occupancy; variant_count::<BoardLayout>()]
 }
@@ -57,12 +74,112 @@ pub(crate) struct CompositeOccupancy {
 
 /// Narrows the given [CompositeOccupancy] into [Occupancy] under the given layout.
 pub(crate) fn layout(rboard: &CompositeOccupancy, layout: BoardLayout) -> &Occupancy {
-    return &((*rboard).boards[layout as usize]);
+    return &rboard.boards[layout as usize];
+}
+
+/// Marks the squares occupied by a piece of the given `color` at `sordinal`, in every [BoardLayout]
+/// of the given [CompositeOccupancy].
+pub(crate) fn fill(board: &mut CompositeOccupancy, color: PieceColor, sordinal: RankwiseSquareOrdinal) {
+    for layout in BoardLayout::ALL {
+        let ordinal = locate::locate(layout, sordinal);
+        board.boards[layout as usize].boards[color as usize] |= bitboards::only(ordinal);
+    }
+}
+
+/// Unmarks the square `sordinal` for the given `color`, in every [BoardLayout] of the given
+/// [CompositeOccupancy]. This is the inverse of [fill].
+pub(crate) fn clear(board: &mut CompositeOccupancy, color: PieceColor, sordinal: RankwiseSquareOrdinal) {
+    for layout in BoardLayout::ALL {
+        let ordinal = locate::locate(layout, sordinal);
+        board.boards[layout as usize].boards[color as usize] &= bitboards::except(ordinal);
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum CastlingSide { Kingside = 0, Queenside = 1 }
+
+/// A set of castling privileges, one bit per [PieceColor] and [CastlingSide]. See [castling_right].
+type CastlingRights = u8;
+
+const NO_CASTLING_RIGHTS: CastlingRights = 0;
+
+/// Produces the [CastlingRights] containing only the privilege of `color` to castle on `side`.
+fn castling_right(color: PieceColor, side: CastlingSide) -> CastlingRights {
+    return 1 << (color as usize * 2 + side as usize);
 }
 
+#[derive(Clone)]
 struct Position {
     ctm: PieceColor, // color to move
+    occupancy: CompositeOccupancy,
+
+    /// The squares occupied by each [PieceKind], irrespective of color, under [BoardLayout::Rankwise].
+    kinds: [Bitboard; variant_count::<PieceKind>()],
+
+    /// The occupant of each square, indexed by [RankwiseSquareOrdinal].
+    squares: [Square; 64],
+
+    castling: CastlingRights,
+
+    /// The square which a pawn skipped over on the previous turn, if any.
+    ep_target: Option<RankwiseSquareOrdinal>,
+
+    /// The number of halfmoves since the last capture or pawn advance.
+    halfmove_clock: u32,
+
+    /// The number of the current move. Begins at one and is incremented after Black moves.
+    fullmove_number: u32,
 
+    /// The digest of this position. It is maintained incrementally, as pieces are placed and removed.
+    key: ZobristKey,
+
+    /// The keys of the positions which preceded this one, oldest first, for detecting repetition.
+    history: Vec<ZobristKey>
+}
+
+impl Position {
+    /// Creates a [Position] without any pieces on the board, White to move.
+    pub(crate) fn empty() -> Self {
+        return Position {
+            ctm: PieceColor::White,
+            occupancy: CompositeOccupancy::default(),
+            kinds: [0; variant_count::<PieceKind>()],
+            squares: [Square { occupant: None }; 64],
+            castling: NO_CASTLING_RIGHTS,
+            ep_target: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            key: 0,
+            history: Vec::new()
+        }
+    }
+}
+
+/// Puts the given `piece` on the vacant square `sordinal`, updating every board of the [Position].
+pub(crate) fn place_piece(position: &mut Position, sordinal: RankwiseSquareOrdinal, piece: Piece) {
+    fill(&mut position.occupancy, piece.color, sordinal);
+    position.kinds[piece.kind as usize] |= bitboards::only(sordinal);
+    position.squares[sordinal].occupant = Some(piece);
+    position.key ^= lookup_piece_key(piece, sordinal);
+}
+
+/// Removes and returns the piece on square `sordinal`, updating every board of the [Position].
+pub(crate) fn remove_piece(position: &mut Position, sordinal: RankwiseSquareOrdinal) -> Option<Piece> {
+    let piece = position.squares[sordinal].occupant.take()?;
+    clear(&mut position.occupancy, piece.color, sordinal);
+    position.kinds[piece.kind as usize] &= bitboards::except(sordinal);
+    position.key ^= lookup_piece_key(piece, sordinal);
+    return Some(piece);
+}
+
+/// Selects the squares, under [BoardLayout::Rankwise], occupied by pieces of the given `color` and `kind`.
+pub(crate) fn select_pieces(position: &Position, color: PieceColor, kind: PieceKind) -> Bitboard {
+    return position.kinds[kind as usize] & select_color(layout(&position.occupancy, BoardLayout::Rankwise), color);
+}
+
+/// Finds the square occupied by the King of the given `color`.
+pub(crate) fn find_king(position: &Position, color: PieceColor) -> RankwiseSquareOrdinal {
+    return select_pieces(position, color, PieceKind::King).trailing_zeros() as RankwiseSquareOrdinal;
 }
 
 type Diagonal = usize;
@@ -82,20 +199,65 @@ pub(crate) fn is_occupied(board: &Occupancy, origin: usize) -> bool {
 /// Describes a pair of locations on the board. Namely, `origin` and `destination`.
 /// The actual format of each coordinate is dependent on the context.
 /// In other words, the coordinates could be in any [BoardLayout].
+///
+/// The lower twelve bits hold the two coordinates. The upper four bits mark special moves.
+/// A castling move is described by the translation of the King alone. An en-passant capture is
+/// described by the translation of the capturing pawn. A promotion additionally names the
+/// [PieceKind] which the pawn becomes.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub(crate) struct Translation { data: u16 }
 
+const CASTLE_FLAG: u16 = 0b0001;
+const EN_PASSANT_FLAG: u16 = 0b0010;
+
+/// Marks a promotion. The lower two bits of the flags then hold the [PieceKind] of the promoted piece.
+const PROMOTION_FLAG: u16 = 0b1000;
+
+/// The kinds of piece which a pawn may be promoted to, indexed by their [PieceKind] ordinal.
+const PROMOTION_KINDS: [PieceKind; 4] = [PieceKind::Rook, PieceKind::Knight, PieceKind::Bishop, PieceKind::Queen];
+
 impl Translation {
     pub(crate) fn origin(self) -> usize { usize::from(self.data & 0b111111) }
     pub(crate) fn destination(self) -> usize { usize::from((self.data >> 6) & 0b111111) }
+    fn flags(self) -> u16 { self.data >> 12 }
+    pub(crate) fn is_castle(self) -> bool { self.flags() == CASTLE_FLAG }
+    pub(crate) fn is_en_passant(self) -> bool { self.flags() == EN_PASSANT_FLAG }
+    pub(crate) fn promotion(self) -> Option<PieceKind> {
+        if self.flags() & PROMOTION_FLAG == 0 { return None; }
+        return Some(PROMOTION_KINDS[usize::from(self.flags() & 0b11)]);
+    }
     pub(crate) fn new(origin: usize, destination: usize) -> Self {
         let mut data = 0u16;
-        data |= (origin as u16 & 0b111111);
-        data |= ((destination as u16 & 0b111111) << 6);
+        data |= origin as u16 & 0b111111;
+        data |= (destination as u16 & 0b111111) << 6;
         return Translation { data }
     }
+    pub(crate) fn castle(origin: usize, destination: usize) -> Self {
+        let mut translation = Translation::new(origin, destination);
+        translation.data |= CASTLE_FLAG << 12;
+        return translation;
+    }
+    pub(crate) fn en_passant(origin: usize, destination: usize) -> Self {
+        let mut translation = Translation::new(origin, destination);
+        translation.data |= EN_PASSANT_FLAG << 12;
+        return translation;
+    }
+    pub(crate) fn promote(origin: usize, destination: usize, kind: PieceKind) -> Self {
+        let mut translation = Translation::new(origin, destination);
+        translation.data |= (PROMOTION_FLAG | kind as u16) << 12;
+        return translation;
+    }
 }
 
 
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = std::env::args().collect();
+    let outcome = match args.get(1).map(String::as_str) {
+        Some("perft") => perft::run(&args[2..]),
+        _ => { uci::run(); Ok(()) }
+    };
+    if let Err(message) = outcome {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
 }
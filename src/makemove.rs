@@ -0,0 +1,178 @@
+use crate::castling_right;
+use crate::CastlingRights;
+use crate::CastlingSide;
+use crate::opponent;
+use crate::Piece;
+use crate::PieceColor;
+use crate::PieceKind;
+use crate::place_piece;
+use crate::Position;
+use crate::remove_piece;
+use crate::Translation;
+use crate::locate::RankwiseSquareOrdinal;
+use crate::movegen::is_en_passant_threatened;
+use crate::movegen::locate_en_passant_victim;
+use crate::movegen::lookup_castling_rook;
+use crate::zobrist::lookup_castling_key;
+use crate::zobrist::lookup_ctm_key;
+use crate::zobrist::lookup_ep_key;
+use crate::zobrist::ZobristKey;
+
+/// Describes the state which [make_move] discards and which [unmake_move] must restore.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Undo {
+    translation: Translation,
+
+    /// The piece which occupied the destination square before the move, if any, or the pawn
+    /// captured en passant.
+    captured: Option<Piece>,
+
+    castling: CastlingRights,
+    ep_target: Option<RankwiseSquareOrdinal>,
+    halfmove_clock: u32,
+    key: ZobristKey
+}
+
+/// Produces the [CastlingRights] which are forfeited when a piece moves from or onto the given square.
+/// Moving the King forfeits both privileges of its color. Moving a Rook, or having it captured,
+/// forfeits the privilege on the Rook's side of the board.
+fn lookup_forfeited_rights(sordinal: RankwiseSquareOrdinal) -> CastlingRights {
+    return match sordinal {
+        0 => castling_right(PieceColor::White, CastlingSide::Queenside),
+        4 => castling_right(PieceColor::White, CastlingSide::Queenside)
+            | castling_right(PieceColor::White, CastlingSide::Kingside),
+        7 => castling_right(PieceColor::White, CastlingSide::Kingside),
+        56 => castling_right(PieceColor::Black, CastlingSide::Queenside),
+        60 => castling_right(PieceColor::Black, CastlingSide::Queenside)
+            | castling_right(PieceColor::Black, CastlingSide::Kingside),
+        63 => castling_right(PieceColor::Black, CastlingSide::Kingside),
+        _ => 0
+    }
+}
+
+/// Applies the given [Translation] to the [Position], moving the piece at the origin to the
+/// destination, and capturing the destination's occupant if there is one. When castling, the
+/// Rook is moved too. When capturing en passant, the pawn behind the destination is removed.
+/// When promoting, the pawn is replaced by the promoted piece. Every [BoardLayout] of the
+/// position's [CompositeOccupancy], and its [ZobristKey], is updated incrementally. The key prior
+/// to the move is appended to the position's history.
+///
+/// The translation must describe a pseudo-legal move for the color to move.
+/// The returned [Undo] must be passed to [unmake_move] to restore the position.
+///
+/// [BoardLayout]: crate::locate::BoardLayout
+/// [CompositeOccupancy]: crate::CompositeOccupancy
+pub(crate) fn make_move(position: &mut Position, translation: Translation) -> Undo {
+    let victim: RankwiseSquareOrdinal = match translation.is_en_passant() {
+        true => locate_en_passant_victim(translation.destination(), position.ctm),
+        false => translation.destination()
+    };
+    let undo = Undo {
+        translation,
+        captured: position.squares[victim].occupant,
+        castling: position.castling,
+        ep_target: position.ep_target,
+        halfmove_clock: position.halfmove_clock,
+        key: position.key
+    };
+    Vec::push(&mut position.history, position.key);
+
+    if undo.captured.is_some() { remove_piece(position, victim); }
+    let piece = remove_piece(position, translation.origin()).unwrap();
+    match translation.promotion() {
+        Some(kind) => place_piece(position, translation.destination(), Piece { color: piece.color, kind }),
+        None => place_piece(position, translation.destination(), piece)
+    }
+    if translation.is_castle() {
+        let rook_translation = lookup_castling_rook(translation.destination());
+        let rook = remove_piece(position, rook_translation.origin()).unwrap();
+        place_piece(position, rook_translation.destination(), rook);
+    }
+
+    position.key ^= lookup_castling_key(position.castling) ^ lookup_ep_key(position.ep_target);
+    position.castling &= !lookup_forfeited_rights(translation.origin());
+    position.castling &= !lookup_forfeited_rights(translation.destination());
+    position.ep_target = None;
+    if piece.kind == PieceKind::Pawn && translation.origin().abs_diff(translation.destination()) == 16
+        && is_en_passant_threatened(position, translation.destination(), opponent(piece.color)) {
+        // The pawn advanced two squares, beside an opposing pawn. The square it skipped over is the
+        // midpoint of the two. Without a pawn to capture en passant, the target is left unset, so
+        // that the position's key matches that of the same position reached otherwise.
+        position.ep_target = Some((translation.origin() + translation.destination()) / 2);
+    }
+    position.key ^= lookup_castling_key(position.castling) ^ lookup_ep_key(position.ep_target) ^ lookup_ctm_key();
+
+    position.halfmove_clock += 1;
+    if undo.captured.is_some() || piece.kind == PieceKind::Pawn { position.halfmove_clock = 0; }
+    if position.ctm == PieceColor::Black { position.fullmove_number += 1; }
+    position.ctm = opponent(position.ctm);
+
+    return undo;
+}
+
+/// Reverts the move described by the given [Undo], restoring the [Position] to its state prior
+/// to the corresponding call to [make_move].
+pub(crate) fn unmake_move(position: &mut Position, undo: Undo) {
+    position.ctm = opponent(position.ctm);
+    if position.ctm == PieceColor::Black { position.fullmove_number -= 1; }
+
+    if undo.translation.is_castle() {
+        let rook_translation = lookup_castling_rook(undo.translation.destination());
+        let rook = remove_piece(position, rook_translation.destination()).unwrap();
+        place_piece(position, rook_translation.origin(), rook);
+    }
+
+    let mut piece = remove_piece(position, undo.translation.destination()).unwrap();
+    if undo.translation.promotion().is_some() { piece.kind = PieceKind::Pawn; }
+    place_piece(position, undo.translation.origin(), piece);
+    if let Some(captured) = undo.captured {
+        let victim: RankwiseSquareOrdinal = match undo.translation.is_en_passant() {
+            true => locate_en_passant_victim(undo.translation.destination(), position.ctm),
+            false => undo.translation.destination()
+        };
+        place_piece(position, victim, captured);
+    }
+
+    position.castling = undo.castling;
+    position.ep_target = undo.ep_target;
+    position.halfmove_clock = undo.halfmove_clock;
+    position.key = undo.key;
+    position.history.pop();
+}
+
+/// Describes the state which [make_null_move] discards and which [unmake_null_move] must restore.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct NullUndo {
+    ep_target: Option<RankwiseSquareOrdinal>,
+    halfmove_clock: u32,
+    key: ZobristKey
+}
+
+/// Passes the turn to the opponent without moving a piece. This is not a legal move, but the search
+/// makes it to learn how strong a position is, even were the opponent to move twice in a row.
+/// The color to move must not be in check.
+///
+/// The halfmove clock is reset, as by an irreversible move, so that no position before the null move
+/// counts towards a repetition after it. See [count_repetitions].
+///
+/// [count_repetitions]: crate::draw::count_repetitions
+pub(crate) fn make_null_move(position: &mut Position) -> NullUndo {
+    let undo = NullUndo { ep_target: position.ep_target, halfmove_clock: position.halfmove_clock, key: position.key };
+    Vec::push(&mut position.history, position.key);
+    position.key ^= lookup_ep_key(position.ep_target) ^ lookup_ctm_key();
+    position.ep_target = None;
+    position.halfmove_clock = 0;
+    if position.ctm == PieceColor::Black { position.fullmove_number += 1; }
+    position.ctm = opponent(position.ctm);
+    return undo;
+}
+
+/// Reverts the null move described by the given [NullUndo]. See [make_null_move].
+pub(crate) fn unmake_null_move(position: &mut Position, undo: NullUndo) {
+    position.ctm = opponent(position.ctm);
+    if position.ctm == PieceColor::Black { position.fullmove_number -= 1; }
+    position.ep_target = undo.ep_target;
+    position.halfmove_clock = undo.halfmove_clock;
+    position.key = undo.key;
+    position.history.pop();
+}
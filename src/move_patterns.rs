@@ -1,8 +1,13 @@
-use std::simd::{LaneCount, Simd, SimdInt, SimdUint, SupportedLaneCount};
+use std::simd::Select;
+use std::simd::Simd;
+use std::simd::cmp::SimdPartialOrd;
+use std::simd::num::SimdInt;
+use std::simd::num::SimdUint;
 use crate::Bitboard;
 use crate::bitboards;
 use crate::PieceColor;
 use crate::locate::RankwiseSquareOrdinal;
+use crate::locate::split_rwc;
 
 pub(crate) struct Pattern<const N: usize> {
     pub(crate) ranks: [i8; N],
@@ -12,20 +17,26 @@ pub(crate) struct Pattern<const N: usize> {
 /// Produces [N], [RankwiseSquareOrdinal]-like quantities, by applying each offset in the given `pattern`
 /// to the given `origin`. The return value will be < 0 or > 63 if the origin is sufficiently
 /// close to the ends of the board and the pattern contains sufficiently large offsets.
+/// Offsets which would carry the origin past the Queenside or Kingside edge of the board, and so
+/// wrap around onto a neighboring rank, also produce a value < 0.
 #[inline]
-pub(crate) fn translate_n<const N: usize>(origin: RankwiseSquareOrdinal, pattern: &Pattern<N>) -> Simd<isize, N>
-    where LaneCount<N>: SupportedLaneCount {
+pub(crate) fn translate_n<const N: usize>(origin: RankwiseSquareOrdinal, pattern: &Pattern<N>) -> Simd<isize, N> {
     let files: Simd<isize, N> = Simd::<i8, N>::cast(Simd::<i8, N>::from_array(pattern.files));
     let ranks: Simd<isize, N> = Simd::<i8, N>::cast(Simd::<i8, N>::from_array(pattern.ranks));
-    return Simd::<isize, N>::splat(origin as isize) + (Simd::<isize, N>::splat(8) * ranks) + files;
+    let destinations = Simd::<isize, N>::splat(origin as isize) + (Simd::<isize, N>::splat(8) * ranks) + files;
+
+    let (_, origin_file) = split_rwc(origin);
+    let destination_files = Simd::<isize, N>::splat(origin_file as isize) + files;
+    let inside = Simd::<isize, N>::simd_ge(destination_files, Simd::<isize, N>::splat(0))
+        & Simd::<isize, N>::simd_le(destination_files, Simd::<isize, N>::splat(7));
+    return inside.select(destinations, Simd::<isize, N>::splat(-1));
 }
 
 #[inline]
-pub(crate) fn instantiate_pattern<const N: usize>(origin: RankwiseSquareOrdinal, pattern: &Pattern<N>) -> Bitboard
-    where LaneCount<N>: SupportedLaneCount {
+pub(crate) fn instantiate_pattern<const N: usize>(origin: RankwiseSquareOrdinal, pattern: &Pattern<N>) -> Bitboard {
 
     let boards = bitboards::only_n(translate_n(origin, pattern));
-    return Simd::<u64, N>::reduce_and(boards)
+    return Simd::<u64, N>::reduce_or(boards)
 }
 
 const fn compile_knight_pattern() -> Pattern<8> {
@@ -127,7 +138,7 @@ const BLACK_PAWN_CAPTURE_PATTERN: Pattern<2> =
     compile_pawn_capture_pattern(PieceColor::Black);
 
 const WHITE_PAWN_CAPTURE_PATTERN: Pattern<2> =
-    compile_pawn_capture_pattern(PieceColor::Black);
+    compile_pawn_capture_pattern(PieceColor::White);
 
 const PAWN_CAPTURE_PATTERN: [Pattern<2>; 2] = [
     BLACK_PAWN_CAPTURE_PATTERN,
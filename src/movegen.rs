@@ -1,18 +1,22 @@
-use std::simd::LaneCount;
-use std::simd::SupportedLaneCount;
 use crate::Bitboard;
 use crate::bitboards;
 use crate::bitlanes;
+use crate::castling_right;
+use crate::CastlingSide;
 use crate::is_occupied;
+use crate::opponent;
+use crate::Piece;
 use crate::PieceColor;
+use crate::PieceKind;
+use crate::Position;
 use crate::CompositeOccupancy;
 use crate::layout;
 use crate::select_color;
 use crate::select_occupied;
+use crate::select_pieces;
 use crate::Translation;
 use crate::locate::BoardLayout;
 use crate::locate::DiagonalSquareCoordinate;
-use crate::locate::FilewiseSquareOrdinal;
 use crate::locate::locate_ad;
 use crate::locate::locate_d;
 use crate::locate::Rank;
@@ -27,6 +31,9 @@ use crate::move_patterns::KNIGHT_PATTERN;
 use crate::move_patterns::lookup_pawn_capture_pattern;
 use crate::move_patterns::Pattern;
 use crate::obstruct::lookup_unobstructed_squares;
+use crate::rays::lookup_between;
+
+pub(crate) type MoveList = Vec<Translation>;
 
 #[derive(Copy, Clone)]
 pub(crate) struct MovingPiece {
@@ -34,14 +41,16 @@ pub(crate) struct MovingPiece {
     color: PieceColor
 }
 
-/// Generates all pseudo-legal moves for a Bishop fixed at `mpiece.origin` of color `mpiece.color`.
-/// A Bishop of that color need not actually exist. This function simply assumes one does.
-/// In other words, the presence of such a Bishop at the given origin **is not** a precondition.
-pub(crate) fn bishop(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
+/// Computes a [Bitboard], under [BoardLayout::Rankwise], where every square a Bishop at `origin`
+/// can slide to is marked. The first occupied square in each direction is marked, regardless of the
+/// color of its occupant.
+pub(crate) fn bishop_reach(origin: RankwiseSquareOrdinal, board: &CompositeOccupancy) -> Bitboard {
+    let mut reach: Bitboard = 0;
+
     // Diagonals
     {
-        let diagonal_coordinate = locate_d(mpiece.origin);
-        let diagonal_board = layout(&board, BoardLayout::Diagonal);
+        let diagonal_coordinate = locate_d(origin);
+        let diagonal_board = layout(board, BoardLayout::Diagonal);
         let diagonal_occupancy = bitlanes::slice_d(diagonal_coordinate.diagonal,
             select_occupied(diagonal_board));
 
@@ -50,26 +59,21 @@ pub(crate) fn bishop(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mu
 
         // Filter out extraneous squares.
         let diagonal_destinations = bitlanes::trim_to(diagonal_destinations,
-            measure_diagonal(8, diagonal_coordinate.diagonal) as u8);
-
-        // Filter out all squares where the occupant's color is equal to the color of the bishop.
-        let diagonal_destinations = diagonal_destinations & !bitlanes::slice_d(
-            diagonal_coordinate.diagonal, select_color(diagonal_board, mpiece.color));
+            measure_diagonal(8, diagonal_coordinate.diagonal + 1) as u8);
 
         bitlanes::scan(diagonal_destinations, |dest_offset| {
             let destination = reverse_locate_d(DiagonalSquareCoordinate {
                 diagonal: diagonal_coordinate.diagonal,
                 offset: dest_offset as usize,
             });
-            let translation = Translation::new(mpiece.origin, destination);
-            Vec::push(moves, translation)
+            reach |= bitboards::only(destination);
         });
     }
 
     // Antidiagonals
     {
-        let antidiagonal_coordinate = locate_ad(mpiece.origin);
-        let antidiagonal_board = layout(&board, BoardLayout::Antidiagonal);
+        let antidiagonal_coordinate = locate_ad(origin);
+        let antidiagonal_board = layout(board, BoardLayout::Antidiagonal);
         let antidiagonal_occupancy = bitlanes::slice_d(antidiagonal_coordinate.diagonal,
             select_occupied(antidiagonal_board));
         let antidiagonal_destinations = lookup_unobstructed_squares(
@@ -77,86 +81,127 @@ pub(crate) fn bishop(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mu
 
         // Filter out extraneous squares.
         let antidiagonal_destinations = bitlanes::trim_to(antidiagonal_destinations,
-            measure_diagonal(8, antidiagonal_coordinate.diagonal) as u8);
-
-        // Filter out all squares where the occupant's color is equal to the color of the bishop.
-        let antidiagonal_destinations = antidiagonal_destinations & !bitlanes::slice_d(
-            antidiagonal_coordinate.diagonal, select_color(antidiagonal_board, mpiece.color));
+            measure_diagonal(8, antidiagonal_coordinate.diagonal + 1) as u8);
 
         bitlanes::scan(antidiagonal_destinations, |dest_offset| {
             let destination = reverse_locate_ad(DiagonalSquareCoordinate {
                 diagonal: antidiagonal_coordinate.diagonal,
                 offset: dest_offset as usize
             });
-            let translation = Translation::new(mpiece.origin, destination);
-            Vec::push(moves, translation);
+            reach |= bitboards::only(destination);
         });
     }
+
+    return reach;
 }
 
-/// Generates all pseudo-legal moves for a Rook fixed at `mpiece.origin` of color `mpiece.color`.
-/// A Rook of that color need not actually exist. This function simply assumes one does.
-/// In other words, the presence of such a Rook at the given origin **is not** a precondition.
-pub(crate) fn rook(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
+/// Computes a [Bitboard], under [BoardLayout::Rankwise], where every square a Rook at `origin`
+/// can slide to is marked. The first occupied square in each direction is marked, regardless of the
+/// color of its occupant.
+pub(crate) fn rook_reach(origin: RankwiseSquareOrdinal, board: &CompositeOccupancy) -> Bitboard {
+    let mut reach: Bitboard = 0;
+    let (rank, file) = split_rwc(origin);
+
     // Ranks
     {
-        let (rank, file) = split_rwc(mpiece.origin);
         let rank_occupancy = bitlanes::slice(rank * 8, select_occupied(layout(board, BoardLayout::Rankwise)));
         let destinations = lookup_unobstructed_squares(file, rank_occupancy);
-        let destinations = destinations & !bitlanes::slice(rank * 8,
-            !select_color(layout(board, BoardLayout::Rankwise), mpiece.color));
-        bitlanes::scan(destinations, |dest_file: u32| {
-            let destination: RankwiseSquareOrdinal = rank * 8 + dest_file as usize;
-            let translation = Translation::new(mpiece.origin, destination);
-            Vec::push(moves, translation);
-        });
+        reach |= Bitboard::from(destinations) << (rank * 8);
     }
 
     // Files
     {
-        let (rank, file) = split_rwc(mpiece.origin);
         let file_occupancy = bitlanes::slice(file * 8, select_occupied(layout(board, BoardLayout::Filewise)));
         let destinations = lookup_unobstructed_squares(rank, file_occupancy);
-        let destinations = destinations & !bitlanes::slice(file,
-            select_color(layout(board, BoardLayout::Filewise), mpiece.color));
         bitlanes::scan(destinations, |dest_rank| {
             let destination: RankwiseSquareOrdinal = dest_rank as usize * 8 + file;
-            let translation = Translation::new(mpiece.origin, destination);
-            Vec::push(moves, translation);
+            reach |= bitboards::only(destination);
         });
     }
-}
 
-pub(crate) fn queen(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
-    rook(mpiece, board, moves);
-    bishop(mpiece, board, moves);
+    return reach;
 }
 
-fn pattern<const N: usize>(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>,
-                           pattern: &Pattern<N>) where LaneCount<N>: SupportedLaneCount {
-    let destinations = instantiate_pattern(mpiece.origin, pattern)
-        & !select_color(layout(board, BoardLayout::Rankwise), mpiece.color);
+pub(crate) fn queen_reach(origin: RankwiseSquareOrdinal, board: &CompositeOccupancy) -> Bitboard {
+    return rook_reach(origin, board) | bishop_reach(origin, board);
+}
 
+/// Pushes a [Translation] from `origin` to each square marked in `destinations`.
+fn push_translations(origin: RankwiseSquareOrdinal, destinations: Bitboard, moves: &mut MoveList) {
     bitboards::scan(destinations, |destination| {
-        let translation = Translation::new(mpiece.origin, destination as usize);
+        let translation = Translation::new(origin, destination as usize);
         Vec::push(moves, translation);
     });
 }
 
-pub(crate) fn knight(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
-    pattern(mpiece, board, moves, &KNIGHT_PATTERN);
+/// Selects the squares which are not occupied by a piece of the moving piece's own color.
+fn select_enterable(mpiece: MovingPiece, board: &CompositeOccupancy) -> Bitboard {
+    return !select_color(layout(board, BoardLayout::Rankwise), mpiece.color);
 }
 
-pub(crate) fn king(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
-    pattern(mpiece, board, moves, &KING_PATTERN);
+/// Generates all pseudo-legal moves for a Bishop fixed at `mpiece.origin` of color `mpiece.color`,
+/// whose destination is marked in `targets`.
+/// A Bishop of that color need not actually exist. This function simply assumes one does.
+/// In other words, the presence of such a Bishop at the given origin **is not** a precondition.
+pub(crate) fn bishop(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
+    let destinations = bishop_reach(mpiece.origin, board) & select_enterable(mpiece, board) & targets;
+    push_translations(mpiece.origin, destinations, moves);
+}
+
+/// Generates all pseudo-legal moves for a Rook fixed at `mpiece.origin` of color `mpiece.color`,
+/// whose destination is marked in `targets`.
+/// A Rook of that color need not actually exist. This function simply assumes one does.
+/// In other words, the presence of such a Rook at the given origin **is not** a precondition.
+pub(crate) fn rook(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
+    let destinations = rook_reach(mpiece.origin, board) & select_enterable(mpiece, board) & targets;
+    push_translations(mpiece.origin, destinations, moves);
+}
+
+pub(crate) fn queen(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
+    rook(mpiece, board, targets, moves);
+    bishop(mpiece, board, targets, moves);
+}
+
+fn pattern<const N: usize>(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard,
+                           moves: &mut MoveList, pattern: &Pattern<N>) {
+    let destinations = instantiate_pattern(mpiece.origin, pattern)
+        & select_enterable(mpiece, board)
+        & targets;
+    push_translations(mpiece.origin, destinations, moves);
+}
+
+pub(crate) fn knight(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
+    pattern(mpiece, board, targets, moves, &KNIGHT_PATTERN);
+}
+
+pub(crate) fn king(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
+    pattern(mpiece, board, targets, moves, &KING_PATTERN);
+}
+
+/// Pushes a [Translation] for a pawn of the given `color` from `origin` to each square marked in
+/// `destinations`. A destination on the final rank yields one promotion for each [PieceKind] the pawn
+/// may become, instead.
+fn push_pawn_translations(origin: RankwiseSquareOrdinal, color: PieceColor, destinations: Bitboard,
+                          moves: &mut MoveList) {
+    let final_rank = lookup_back_rank(opponent(color));
+    bitboards::scan(destinations, |destination| {
+        let destination = destination as RankwiseSquareOrdinal;
+        if destination / 8 != final_rank {
+            Vec::push(moves, Translation::new(origin, destination));
+            return;
+        }
+        for kind in [PieceKind::Queen, PieceKind::Knight, PieceKind::Rook, PieceKind::Bishop] {
+            Vec::push(moves, Translation::promote(origin, destination, kind));
+        }
+    });
 }
 
-pub(crate) fn pawn_step(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
+pub(crate) fn pawn_step(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
     let (origin_rank, origin_file) = split_rwc(mpiece.origin);
     let destination_rank: Rank = (origin_rank as i8 + lookup_pawn_direction(mpiece.color)) as Rank;
     let destination: RankwiseSquareOrdinal = 8 * destination_rank + origin_file;
-    let bb = bitboards::only(destination) & !select_occupied(layout(board, BoardLayout::Rankwise));
-    bitboards::scan(bb, |d| Vec::push(moves, Translation::new(mpiece.origin, d as usize)));
+    let bb = bitboards::only(destination) & !select_occupied(layout(board, BoardLayout::Rankwise)) & targets;
+    push_pawn_translations(mpiece.origin, mpiece.color, bb, moves);
 }
 
 fn lookup_pawn_direction(color: PieceColor) -> i8 {
@@ -169,7 +214,7 @@ fn lookup_pawn_birth_rank(color: PieceColor) -> i8 {
     return BIRTH_RANK[color as usize];
 }
 
-pub(crate) fn pawn_2step(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
+pub(crate) fn pawn_2step(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
     let rw_board = layout(board, BoardLayout::Rankwise);
     let direction = lookup_pawn_direction(mpiece.color);
     let birth_rank = lookup_pawn_birth_rank(mpiece.color);
@@ -184,23 +229,176 @@ pub(crate) fn pawn_2step(mpiece: MovingPiece, board: &CompositeOccupancy, moves:
     if is_occupied(rw_board, lava_rank * 8 + origin_file) { return; }
     // Ensure destination square isn't blocked.
     if is_occupied(rw_board, destination) { return; }
+    if bitboards::only(destination) & targets == 0 { return; }
 
     Vec::push(moves, Translation::new(mpiece.origin, destination))
 }
 
-pub(crate) fn pawn_capture(mpiece: MovingPiece, board: &CompositeOccupancy, moves: &mut Vec<Translation>) {
+pub(crate) fn pawn_capture(mpiece: MovingPiece, board: &CompositeOccupancy, targets: Bitboard, moves: &mut MoveList) {
     let rw_board = layout(board, BoardLayout::Rankwise);
 
     let bb: Bitboard =
         instantiate_pattern(mpiece.origin, lookup_pawn_capture_pattern(mpiece.color))
         & !select_color(rw_board, mpiece.color)
-        & select_occupied(rw_board);
+        & select_occupied(rw_board)
+        & targets;
 
-    bitboards::scan(bb, |destination| {
-        let translation = Translation::new(mpiece.origin, destination as usize);
-        Vec::push(moves, translation);
+    push_pawn_translations(mpiece.origin, mpiece.color, bb, moves);
+}
+
+fn lookup_back_rank(color: PieceColor) -> Rank {
+    const BACK_RANK: [Rank; 2] = [7, 0];
+    return BACK_RANK[color as usize];
+}
+
+/// Produces the [Translation] of the Rook which accompanies a King castling onto `king_destination`.
+pub(crate) fn lookup_castling_rook(king_destination: RankwiseSquareOrdinal) -> Translation {
+    let (rank, file) = split_rwc(king_destination);
+    return match file {
+        6 => Translation::new(rank * 8 + 7, rank * 8 + 5),
+        2 => Translation::new(rank * 8, rank * 8 + 3),
+        _ => panic!("Expected the destination of a castling King.")
+    }
+}
+
+/// Generates the castling moves of the color to move, whose King's destination is marked in `targets`.
+/// A castling move is generated only if the color retains the privilege to castle on that side,
+/// and every square between the King and the Rook is vacant. Whether the King is in check, or
+/// passes through or onto an attacked square, is **not** considered. See also,
+/// [generate_legal_moves](crate::legal::generate_legal_moves).
+pub(crate) fn castle(position: &Position, targets: Bitboard, moves: &mut MoveList) {
+    let rank = lookup_back_rank(position.ctm);
+    let king: RankwiseSquareOrdinal = rank * 8 + 4;
+    let occupied = select_occupied(layout(&position.occupancy, BoardLayout::Rankwise));
+    let own_king = Some(Piece { color: position.ctm, kind: PieceKind::King });
+    let own_rook = Some(Piece { color: position.ctm, kind: PieceKind::Rook });
+    if position.squares[king].occupant != own_king { return; }
+
+    for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+        if position.castling & castling_right(position.ctm, side) == 0 { continue; }
+        let (destination_file, rook_file) = match side {
+            CastlingSide::Kingside => (6, 7),
+            CastlingSide::Queenside => (2, 0)
+        };
+        let destination: RankwiseSquareOrdinal = rank * 8 + destination_file;
+        let rook: RankwiseSquareOrdinal = rank * 8 + rook_file;
+        if position.squares[rook].occupant != own_rook { continue; }
+        if lookup_between(king, rook) & occupied > 0 { continue; }
+        if bitboards::only(destination) & targets == 0 { continue; }
+        Vec::push(moves, Translation::castle(king, destination));
+    }
+}
+
+/// Locates the pawn which is captured by an en-passant capture onto `ep_target`, given the color
+/// of the capturing pawn.
+pub(crate) fn locate_en_passant_victim(ep_target: RankwiseSquareOrdinal, color: PieceColor) -> RankwiseSquareOrdinal {
+    return (ep_target as isize - 8 * lookup_pawn_direction(color) as isize) as RankwiseSquareOrdinal;
+}
+
+/// Determines whether a pawn of the given `color` stands beside the opposing pawn at `victim`, on the
+/// same rank, and so could capture it en passant were it to have just advanced two squares.
+pub(crate) fn is_en_passant_threatened(position: &Position, victim: RankwiseSquareOrdinal, color: PieceColor) -> bool {
+    let (_, file) = split_rwc(victim);
+    let mut beside: Bitboard = 0;
+    if file > 0 { beside |= bitboards::only(victim - 1); }
+    if file < 7 { beside |= bitboards::only(victim + 1); }
+    return select_pieces(position, color, PieceKind::Pawn) & beside > 0;
+}
+
+/// Generates the en-passant capture onto the en-passant target of the [Position] for a pawn fixed at
+/// `mpiece.origin`, if the pawn is positioned to make one, an opposing pawn stands to be captured,
+/// and the target is vacant. The capture is generated only if the square of the captured pawn,
+/// rather than the destination, is marked in `targets`.
+pub(crate) fn pawn_en_passant(mpiece: MovingPiece, position: &Position, targets: Bitboard, moves: &mut MoveList) {
+    let Some(ep_target) = position.ep_target else { return; };
+    let victim = locate_en_passant_victim(ep_target, mpiece.color);
+    if bitboards::only(victim) & targets == 0 { return; }
+    if select_pieces(position, opponent(mpiece.color), PieceKind::Pawn) & bitboards::only(victim) == 0 { return; }
+    let vacant = !select_occupied(layout(&position.occupancy, BoardLayout::Rankwise));
+    let bb = instantiate_pattern(mpiece.origin, lookup_pawn_capture_pattern(mpiece.color))
+        & bitboards::only(ep_target) & vacant;
+    bitboards::scan(bb, |d| Vec::push(moves, Translation::en_passant(mpiece.origin, d as usize)));
+}
+
+/// Generates the pseudo-legal moves of the piece of the color to move standing at `origin`, whose
+/// destination is marked in `targets`. Nothing is generated if the square holds no such piece.
+pub(crate) fn generate_from(position: &Position, origin: RankwiseSquareOrdinal, targets: Bitboard, moves: &mut MoveList) {
+    let board = &position.occupancy;
+    let Some(piece) = position.squares[origin].occupant else { return; };
+    if piece.color != position.ctm { return; }
+    let mpiece = MovingPiece { origin, color: position.ctm };
+    match piece.kind {
+        PieceKind::Rook => rook(mpiece, board, targets, moves),
+        PieceKind::Knight => knight(mpiece, board, targets, moves),
+        PieceKind::Bishop => bishop(mpiece, board, targets, moves),
+        PieceKind::Queen => queen(mpiece, board, targets, moves),
+        PieceKind::King => {
+            king(mpiece, board, targets, moves);
+            castle(position, targets, moves);
+        },
+        PieceKind::Pawn => {
+            pawn_step(mpiece, board, targets, moves);
+            pawn_2step(mpiece, board, targets, moves);
+            pawn_capture(mpiece, board, targets, moves);
+            pawn_en_passant(mpiece, position, targets, moves);
+        }
+    }
+}
+
+/// Generates the pseudo-legal moves of every piece belonging to the color to move, whose
+/// destination is marked in `targets`.
+fn generate(position: &Position, targets: Bitboard, moves: &mut MoveList) {
+    let pieces = select_color(layout(&position.occupancy, BoardLayout::Rankwise), position.ctm);
+    bitboards::scan(pieces, |origin| generate_from(position, origin as RankwiseSquareOrdinal, targets, moves));
+}
+
+/// Generates every pseudo-legal move available to the color to move. A pseudo-legal move obeys the
+/// movement rules of the piece, but might leave the mover's own King in check.
+pub(crate) fn generate_moves(position: &Position, moves: &mut MoveList) {
+    generate(position, bitboards::OPAQUE, moves);
+}
+
+/// Generates the pseudo-legal moves of the color to move which capture an opponent's piece.
+pub(crate) fn generate_captures(position: &Position, moves: &mut MoveList) {
+    let rw_board = layout(&position.occupancy, BoardLayout::Rankwise);
+    generate(position, select_color(rw_board, opponent(position.ctm)), moves);
+}
+
+/// Generates the pseudo-legal moves of the color to move which do not capture.
+pub(crate) fn generate_quiets(position: &Position, moves: &mut MoveList) {
+    let rw_board = layout(&position.occupancy, BoardLayout::Rankwise);
+    generate(position, !select_occupied(rw_board), moves);
+}
+
+/// Generates the pseudo-legal pawn advances of the color to move which promote without capturing.
+/// Promotions by capture are generated by [generate_captures].
+pub(crate) fn generate_promotions(position: &Position, moves: &mut MoveList) {
+    let board = &position.occupancy;
+    let final_rank = lookup_back_rank(opponent(position.ctm));
+    let final_rank_squares: Bitboard = 0b11111111 << (final_rank * 8);
+
+    // Only a pawn on the rank preceding the final rank can promote by advancing.
+    let penultimate_rank = (final_rank as i8 - lookup_pawn_direction(position.ctm)) as Rank;
+    let pawns = select_pieces(position, position.ctm, PieceKind::Pawn) & (0b11111111 << (penultimate_rank * 8));
+    bitboards::scan(pawns, |origin| {
+        let mpiece = MovingPiece { origin: origin as RankwiseSquareOrdinal, color: position.ctm };
+        pawn_step(mpiece, board, final_rank_squares, moves);
     });
 }
 
-// TODO: Enpassant
-// TODO: Castling
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use crate::Position;
+    use crate::legal::generate_legal_moves;
+    use crate::locate::parse_algebraic;
+    use crate::movegen::MoveList;
+
+    #[test]
+    fn en_passant_requires_victim() {
+        let mut position = Position::from_fen("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1").unwrap();
+        position.ep_target = parse_algebraic("e3");
+        let mut moves = MoveList::new();
+        generate_legal_moves(&position, &mut moves);
+        assert!(moves.iter().all(|translation| !translation.is_en_passant()));
+    }
+}
@@ -0,0 +1,308 @@
+use std::mem::take;
+use crate::Piece;
+use crate::PieceKind;
+use crate::Position;
+use crate::Translation;
+use crate::eval::PIECE_VALUES;
+use crate::legal::generate_legal_captures;
+use crate::legal::generate_legal_quiets;
+use crate::legal::is_legal;
+use crate::locate::RankwiseSquareOrdinal;
+use crate::movegen::MoveList;
+use crate::search::MAX_PLY;
+use crate::search::Score;
+use crate::see::see;
+
+/// Estimates the material gained by the given capture or promotion, for ordering and pruning.
+pub(crate) fn estimate_gain(position: &Position, translation: Translation) -> Score {
+    let captured = match translation.is_en_passant() {
+        true => PIECE_VALUES[PieceKind::Pawn as usize],
+        false => position.squares[translation.destination()].occupant
+            .map_or(0, |piece| PIECE_VALUES[piece.kind as usize])
+    };
+    let promoted = translation.promotion()
+        .map_or(0, |kind| PIECE_VALUES[kind as usize] - PIECE_VALUES[PieceKind::Pawn as usize]);
+    return captured + promoted;
+}
+
+/// Scores a capture by Most Valuable Victim, Least Valuable Aggressor. That is, by the value of the
+/// captured piece, and among equal victims, cheaper capturing pieces first.
+pub(crate) fn mvv_lva(position: &Position, translation: Translation) -> i32 {
+    let aggressor = position.squares[translation.origin()].occupant.unwrap().kind;
+    return estimate_gain(position, translation) * 1000 - PIECE_VALUES[aggressor as usize];
+}
+
+/// Determines whether the given move neither captures a piece nor promotes a pawn.
+pub(crate) fn is_quiet(position: &Position, translation: Translation) -> bool {
+    return position.squares[translation.destination()].occupant.is_none()
+        && !translation.is_en_passant()
+        && translation.promotion().is_none();
+}
+
+/// The move which led to a position. That is, the piece which moved and the square it moved to.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct PriorMove { pub(crate) piece: Piece, pub(crate) destination: RankwiseSquareOrdinal }
+
+/// Identifies a piece by its color and kind, for indexing history tables.
+fn index_piece(piece: Piece) -> usize { return piece.color as usize * 6 + piece.kind as usize; }
+
+/// The greatest magnitude of a history score. See [apply_gravity].
+pub(crate) const MAX_HISTORY: i32 = 16384;
+
+/// Adjusts a history score by the given bonus, scaled down as the score nears [MAX_HISTORY], so that
+/// the score never exceeds it and recent outcomes outweigh old ones.
+fn apply_gravity(entry: &mut i32, bonus: i32) {
+    *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+}
+
+/// Remembers which quiet moves caused cutoffs, so that they may be tried early elsewhere.
+pub(crate) struct History {
+    /// The success of each move, irrespective of the position, indexed by the [PieceColor] ordinal of
+    /// the mover, then by origin and destination.
+    ///
+    /// [PieceColor]: crate::PieceColor
+    butterfly: Box<[[[i32; 64]; 64]; 2]>,
+
+    /// The success of each move, as a reply to the [PriorMove]. Indexed by the prior move's piece
+    /// and destination, and then by the moved piece and its destination.
+    continuation: Vec<[[i32; 64]; 12]>,
+
+    /// The latest quiet move to cause a cutoff as a reply to each [PriorMove], indexed as above.
+    countermoves: Vec<Option<Translation>>,
+
+    /// The latest two quiet moves to cause a cutoff at each ply, the most recent first.
+    killers: [[Option<Translation>; 2]; MAX_PLY]
+}
+
+impl History {
+    pub(crate) fn new() -> Self {
+        return History {
+            butterfly: Box::new([[[0; 64]; 64]; 2]),
+            continuation: vec![[[0; 64]; 12]; 12 * 64],
+            countermoves: vec![None; 12 * 64],
+            killers: [[None; 2]; MAX_PLY]
+        };
+    }
+
+    pub(crate) fn killers(&self, ply: usize) -> [Option<Translation>; 2] { return self.killers[ply]; }
+
+    pub(crate) fn countermove(&self, prior: Option<PriorMove>) -> Option<Translation> {
+        let prior = prior?;
+        return self.countermoves[index_piece(prior.piece) * 64 + prior.destination];
+    }
+
+    /// Scores a quiet move of the given position by its butterfly and continuation history.
+    fn score_quiet(&self, position: &Position, prior: Option<PriorMove>, translation: Translation) -> i32 {
+        let piece = position.squares[translation.origin()].occupant.unwrap();
+        let mut score = self.butterfly[piece.color as usize][translation.origin()][translation.destination()];
+        if let Some(prior) = prior {
+            let replies = &self.continuation[index_piece(prior.piece) * 64 + prior.destination];
+            score += replies[index_piece(piece)][translation.destination()];
+        }
+        return score;
+    }
+
+    fn update(&mut self, position: &Position, prior: Option<PriorMove>, translation: Translation, bonus: i32) {
+        let piece = position.squares[translation.origin()].occupant.unwrap();
+        apply_gravity(&mut self.butterfly[piece.color as usize][translation.origin()][translation.destination()], bonus);
+        if let Some(prior) = prior {
+            let replies = &mut self.continuation[index_piece(prior.piece) * 64 + prior.destination];
+            apply_gravity(&mut replies[index_piece(piece)][translation.destination()], bonus);
+        }
+    }
+
+    /// Records that the quiet move `best` caused a cutoff at the given ply, at the given depth, after
+    /// the quiet moves `tried` failed to. The former is rewarded, and the latter are penalized.
+    pub(crate) fn reward(&mut self, position: &Position, prior: Option<PriorMove>, ply: usize, best: Translation,
+                         depth: u32, tried: &[Translation]) {
+        let bonus = (depth * depth).min(400) as i32 * 16;
+        self.update(position, prior, best, bonus);
+        for &translation in tried { self.update(position, prior, translation, -bonus); }
+
+        if self.killers[ply][0] != Some(best) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(best);
+        }
+        if let Some(prior) = prior {
+            self.countermoves[index_piece(prior.piece) * 64 + prior.destination] = Some(best);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Stage { TtMove, GenerateCaptures, GoodCaptures, Refutations, GenerateQuiets, Quiets, BadCaptures, Done }
+
+/// Yields the legal moves of a position, best first by estimate, in stages:
+///
+/// 1. The best move recorded in the transposition table.
+/// 2. Captures and promotions which do not lose material, by [mvv_lva].
+/// 3. The killer moves of the ply, and then the countermove of the [PriorMove].
+/// 4. The remaining quiet moves, by [History].
+/// 5. Captures which lose material, by [see].
+///
+/// Each stage is generated only once the previous stage is exhausted, so no effort is wasted on
+/// later stages when an early move causes a cutoff. The position must not change between calls to
+/// [MovePicker::next], except by moves which are unmade before the next call.
+pub(crate) struct MovePicker {
+    stage: Stage,
+    tt_move: Option<Translation>,
+
+    /// The killer moves, followed by the countermove.
+    refutations: [Option<Translation>; 3],
+    refutation_index: usize,
+
+    /// The moves of the current stage, with their scores. Those before `index` have been yielded.
+    moves: Vec<(Translation, i32)>,
+    index: usize,
+    bad_captures: Vec<(Translation, i32)>
+}
+
+impl MovePicker {
+    pub(crate) fn new(tt_move: Option<Translation>, killers: [Option<Translation>; 2],
+                      countermove: Option<Translation>) -> Self {
+        return MovePicker {
+            stage: Stage::TtMove,
+            tt_move,
+            refutations: [killers[0], killers[1], countermove],
+            refutation_index: 0,
+            moves: Vec::new(),
+            index: 0,
+            bad_captures: Vec::new()
+        };
+    }
+
+    /// Yields the highest scoring move of the current stage which has not been yielded yet.
+    fn select_best(&mut self) -> Option<Translation> {
+        if self.index >= self.moves.len() { return None; }
+        let (best, _) = self.moves[self.index..].iter().enumerate()
+            .max_by_key(|(_, (_, score))| *score).unwrap();
+        self.moves.swap(self.index, self.index + best);
+        self.index += 1;
+        return Some(self.moves[self.index - 1].0);
+    }
+
+    fn is_refutation(&self, translation: Translation) -> bool {
+        return self.refutations.contains(&Some(translation));
+    }
+
+    /// Yields the next move, or `None` once every legal move has been yielded. `history` and `prior`
+    /// order the quiet moves.
+    pub(crate) fn next(&mut self, position: &Position, history: &History, prior: Option<PriorMove>)
+        -> Option<Translation> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if let Some(tt_move) = self.tt_move.filter(|&tt_move| is_legal(position, tt_move)) {
+                        return Some(tt_move);
+                    }
+                    self.tt_move = None;
+                },
+                Stage::GenerateCaptures => {
+                    let mut captures = MoveList::new();
+                    generate_legal_captures(position, &mut captures);
+                    for translation in captures {
+                        if Some(translation) == self.tt_move { continue; }
+                        let scored = (translation, mvv_lva(position, translation));
+                        match translation.promotion().is_some() || see(position, translation) >= 0 {
+                            true => Vec::push(&mut self.moves, scored),
+                            false => Vec::push(&mut self.bad_captures, scored)
+                        }
+                    }
+                    self.stage = Stage::GoodCaptures;
+                },
+                Stage::GoodCaptures => {
+                    if let Some(translation) = self.select_best() { return Some(translation); }
+                    self.stage = Stage::Refutations;
+                },
+                Stage::Refutations => {
+                    while self.refutation_index < self.refutations.len() {
+                        let i = self.refutation_index;
+                        self.refutation_index += 1;
+                        let Some(refutation) = self.refutations[i] else { continue; };
+                        if Some(refutation) == self.tt_move || self.refutations[..i].contains(&Some(refutation)) {
+                            continue;
+                        }
+                        if is_quiet(position, refutation) && is_legal(position, refutation) { return Some(refutation); }
+                    }
+                    self.stage = Stage::GenerateQuiets;
+                },
+                Stage::GenerateQuiets => {
+                    let mut quiets = MoveList::new();
+                    generate_legal_quiets(position, &mut quiets);
+                    self.moves.clear();
+                    self.index = 0;
+                    for translation in quiets {
+                        if Some(translation) == self.tt_move || self.is_refutation(translation) { continue; }
+                        Vec::push(&mut self.moves, (translation, history.score_quiet(position, prior, translation)));
+                    }
+                    self.stage = Stage::Quiets;
+                },
+                Stage::Quiets => {
+                    if let Some(translation) = self.select_best() { return Some(translation); }
+                    self.moves = take(&mut self.bad_captures);
+                    self.index = 0;
+                    self.stage = Stage::BadCaptures;
+                },
+                Stage::BadCaptures => {
+                    if let Some(translation) = self.select_best() { return Some(translation); }
+                    self.stage = Stage::Done;
+                },
+                Stage::Done => return None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Position;
+    use crate::Translation;
+    use crate::legal::is_legal;
+    use crate::movegen::generate_moves;
+    use crate::movegen::MoveList;
+    use crate::movepick::History;
+    use crate::movepick::is_quiet;
+    use crate::movepick::MovePicker;
+    use crate::notation::parse_coordinates;
+    use crate::see::see;
+
+    #[test]
+    fn stages() {
+        let position = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let parse = |coordinates| parse_coordinates(&position, coordinates).unwrap();
+        let tt_move = parse("d5e6");
+        let refutations = [parse("a2a3"), parse("b2b3"), parse("g2g3")];
+        let mut picker = MovePicker::new(Some(tt_move), [Some(refutations[0]), Some(refutations[1])],
+            Some(refutations[2]));
+        let history = History::new();
+        let mut picked: Vec<Translation> = Vec::new();
+        while let Some(translation) = picker.next(&position, &history, None) { Vec::push(&mut picked, translation); }
+
+        // Every legal move is yielded, exactly once.
+        let mut moves = MoveList::new();
+        generate_moves(&position, &mut moves);
+        let mut expected: Vec<u16> = moves.iter().filter(|&&translation| is_legal(&position, translation))
+            .map(|translation| translation.data).collect();
+        let mut actual: Vec<u16> = picked.iter().map(|translation| translation.data).collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+
+        // The TT move comes first. Then come good captures, the refutations in order, the other quiet
+        // moves, and finally the captures which lose material.
+        assert_eq!(picked[0], tt_move);
+        let stage = |translation: Translation| match is_quiet(&position, translation) {
+            true if refutations.contains(&translation) => 1,
+            true => 2,
+            false if see(&position, translation) >= 0 => 0,
+            false => 3
+        };
+        let stages: Vec<i32> = picked[1..].iter().map(|&translation| stage(translation)).collect();
+        assert!(stages.is_sorted(), "{:?}", stages);
+        for expected in 0..4 { assert!(stages.contains(&expected)); }
+        let yielded: Vec<Translation> = picked.iter().copied().filter(|translation| refutations.contains(translation))
+            .collect();
+        assert_eq!(yielded, refutations);
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+use crate::PieceKind;
+use crate::Position;
+use crate::Translation;
+use crate::legal::generate_legal_moves;
+use crate::locate::format_algebraic;
+use crate::locate::parse_algebraic;
+use crate::movegen::MoveList;
+
+/// Formats the [Translation] in coordinate notation, as used by the Universal Chess Interface.
+/// That is, the origin and then the destination, each named as by [format_algebraic], followed by
+/// the lowercase letter of the promoted piece, if any. For instance, `e2e4`, or `e7e8q`.
+/// A castling move is written as the move of the king, for instance `e1g1`.
+impl fmt::Display for Translation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", format_algebraic(self.origin()), format_algebraic(self.destination()))?;
+        if let Some(kind) = self.promotion() {
+            write!(f, "{}", match kind {
+                PieceKind::Rook => 'r',
+                PieceKind::Knight => 'n',
+                PieceKind::Bishop => 'b',
+                _ => 'q'
+            })?;
+        }
+        return Ok(());
+    }
+}
+
+fn parse_promotion(c: u8) -> Option<PieceKind> {
+    return match c {
+        b'r' => Some(PieceKind::Rook),
+        b'n' => Some(PieceKind::Knight),
+        b'b' => Some(PieceKind::Bishop),
+        b'q' => Some(PieceKind::Queen),
+        _ => None
+    }
+}
+
+/// Finds the legal move of the given [Position] which is written as `text` in coordinate notation.
+/// See also, the [Display](fmt::Display) implementation of [Translation].
+///
+/// The squares are read by [parse_algebraic]. The position supplies what the text leaves unsaid,
+/// namely whether the move castles or captures en passant. `None` is produced if the text is
+/// malformed or names no legal move.
+pub(crate) fn parse_coordinates(position: &Position, text: &str) -> Option<Translation> {
+    if !text.is_ascii() || !(4..=5).contains(&text.len()) { return None; }
+    let origin = parse_algebraic(&text[0..2])?;
+    let destination = parse_algebraic(&text[2..4])?;
+    let promotion = match text.as_bytes().get(4) {
+        Some(&c) => Some(parse_promotion(c)?),
+        None => None
+    };
+
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &mut moves);
+    return moves.into_iter().find(|translation| translation.origin() == origin
+        && translation.destination() == destination
+        && translation.promotion() == promotion);
+}
@@ -0,0 +1,78 @@
+use std::time::Instant;
+use crate::Position;
+use crate::Translation;
+use crate::fen::STARTING_FEN;
+use crate::legal::generate_legal_moves;
+use crate::makemove::make_move;
+use crate::makemove::unmake_move;
+use crate::movegen::MoveList;
+
+/// Counts the leaf nodes of the tree of legal moves rooted at the given [Position], `depth` plies deep.
+pub(crate) fn perft(position: &mut Position, depth: u32) -> u64 {
+    if depth == 0 { return 1; }
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &mut moves);
+
+    // The leaves need not be visited to be counted.
+    if depth == 1 { return moves.len() as u64; }
+
+    let mut nodes: u64 = 0;
+    for translation in moves {
+        let undo = make_move(position, translation);
+        nodes += perft(position, depth - 1);
+        unmake_move(position, undo);
+    }
+    return nodes;
+}
+
+/// Performs [perft] for each legal move of the given [Position], pairing the move with the number
+/// of leaf nodes beneath it.
+pub(crate) fn divide(position: &mut Position, depth: u32) -> Vec<(Translation, u64)> {
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &mut moves);
+
+    let mut counts: Vec<(Translation, u64)> = Vec::with_capacity(moves.len());
+    for translation in moves {
+        let undo = make_move(position, translation);
+        Vec::push(&mut counts, (translation, perft(position, depth.saturating_sub(1))));
+        unmake_move(position, undo);
+    }
+    return counts;
+}
+
+/// Runs the `perft` command. The arguments are an optional `divide`, the depth, and then an
+/// optional FEN record, which need not be quoted. The starting position is used when the record
+/// is omitted. In `divide` mode, the node count beneath each root move is listed in the format
+/// of Stockfish's `go perft` command.
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let (divided, args) = match args.first().map(String::as_str) {
+        Some("divide") => (true, &args[1..]),
+        _ => (false, args)
+    };
+    let depth: u32 = args.first()
+        .ok_or(String::from("usage: perft [divide] <depth> [<fen>]"))?
+        .parse().map_err(|_| format!("invalid depth '{}'", args[0]))?;
+    let record = match args.len() > 1 {
+        true => args[1..].join(" "),
+        false => String::from(STARTING_FEN)
+    };
+    let mut position = Position::from_fen(&record).map_err(|error| error.to_string())?;
+
+    let start = Instant::now();
+    let nodes: u64 = match divided {
+        true => {
+            let counts = divide(&mut position, depth);
+            for (translation, count) in &counts {
+                println!("{}: {}", translation, count);
+            }
+            println!();
+            counts.iter().map(|(_, count)| count).sum()
+        },
+        false => perft(&mut position, depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("Nodes searched: {}", nodes);
+    println!("Nodes/second: {}", (nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64);
+    return Ok(());
+}
@@ -0,0 +1,75 @@
+use crate::Bitboard;
+use crate::locate::RankwiseSquareOrdinal;
+
+/// Computes the rank and file steps which lead from `a` towards `b`, if the two squares share a rank,
+/// file, diagonal or antidiagonal. Otherwise, or if the squares are equal, produces `None`.
+const fn align(a: RankwiseSquareOrdinal, b: RankwiseSquareOrdinal) -> Option<(isize, isize)> {
+    let rank_delta = (b / 8) as isize - (a / 8) as isize;
+    let file_delta = (b % 8) as isize - (a % 8) as isize;
+    if rank_delta == 0 && file_delta == 0 { return None; }
+    if rank_delta != 0 && file_delta != 0 && rank_delta.abs() != file_delta.abs() { return None; }
+    return Some((rank_delta.signum(), file_delta.signum()));
+}
+
+/// Marks every square reached by repeatedly stepping from `origin` in the given direction,
+/// until the edge of the board. The origin itself is not marked.
+const fn walk(origin: RankwiseSquareOrdinal, rank_step: isize, file_step: isize) -> Bitboard {
+    let mut squares: Bitboard = 0;
+    let mut rank = (origin / 8) as isize;
+    let mut file = (origin % 8) as isize;
+    loop {
+        rank += rank_step;
+        file += file_step;
+        if rank < 0 || rank > 7 || file < 0 || file > 7 { break; }
+        squares |= 1 << (rank * 8 + file);
+    }
+    return squares;
+}
+
+const RAY_TABLE_SIZE: usize = 64 /* origins */ * 64 /* destinations */;
+
+const fn compile_between_table() -> [Bitboard; RAY_TABLE_SIZE] {
+    let mut table: [Bitboard; RAY_TABLE_SIZE] = [0; RAY_TABLE_SIZE];
+    let mut i = 0;
+    loop {
+        if i >= RAY_TABLE_SIZE { break; }
+        let a = i / 64;
+        let b = i % 64;
+        if let Some((rank_step, file_step)) = align(a, b) {
+            table[i] = walk(a, rank_step, file_step) & walk(b, -rank_step, -file_step);
+        }
+        i += 1;
+    }
+    return table;
+}
+
+const fn compile_line_table() -> [Bitboard; RAY_TABLE_SIZE] {
+    let mut table: [Bitboard; RAY_TABLE_SIZE] = [0; RAY_TABLE_SIZE];
+    let mut i = 0;
+    loop {
+        if i >= RAY_TABLE_SIZE { break; }
+        let a = i / 64;
+        let b = i % 64;
+        if let Some((rank_step, file_step)) = align(a, b) {
+            table[i] = walk(a, rank_step, file_step) | walk(a, -rank_step, -file_step) | (1 << a);
+        }
+        i += 1;
+    }
+    return table;
+}
+
+static BETWEEN_TABLE: [Bitboard; RAY_TABLE_SIZE] = compile_between_table();
+
+static LINE_TABLE: [Bitboard; RAY_TABLE_SIZE] = compile_line_table();
+
+/// Produces a [Bitboard] marking the squares strictly between `a` and `b`. If the two squares do
+/// not share a rank, file, diagonal or antidiagonal, no squares are marked.
+pub(crate) fn lookup_between(a: RankwiseSquareOrdinal, b: RankwiseSquareOrdinal) -> Bitboard {
+    return BETWEEN_TABLE[a * 64 + b];
+}
+
+/// Produces a [Bitboard] marking every square on the rank, file, diagonal or antidiagonal which
+/// passes through both `a` and `b`, from edge to edge. If there is no such line, no squares are marked.
+pub(crate) fn lookup_line(a: RankwiseSquareOrdinal, b: RankwiseSquareOrdinal) -> Bitboard {
+    return LINE_TABLE[a * 64 + b];
+}
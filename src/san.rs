@@ -0,0 +1,280 @@
+use crate::PieceKind;
+use crate::Position;
+use crate::Translation;
+use crate::legal::generate_legal_moves;
+use crate::legal::in_check;
+use crate::locate::format_algebraic;
+use crate::locate::parse_algebraic;
+use crate::locate::split_rwc;
+use crate::makemove::make_move;
+use crate::movegen::MoveList;
+
+fn format_kind(kind: PieceKind) -> char {
+    return match kind {
+        PieceKind::Rook => 'R',
+        PieceKind::Knight => 'N',
+        PieceKind::Bishop => 'B',
+        PieceKind::Queen => 'Q',
+        PieceKind::King => 'K',
+        PieceKind::Pawn => 'P'
+    }
+}
+
+fn parse_kind(c: char) -> Option<PieceKind> {
+    return match c {
+        'R' => Some(PieceKind::Rook),
+        'N' => Some(PieceKind::Knight),
+        'B' => Some(PieceKind::Bishop),
+        'Q' => Some(PieceKind::Queen),
+        'K' => Some(PieceKind::King),
+        _ => None
+    }
+}
+
+fn is_capture(position: &Position, translation: Translation) -> bool {
+    return translation.is_en_passant() || position.squares[translation.destination()].occupant.is_some();
+}
+
+/// Formats the given legal move of the given [Position] in Standard Algebraic Notation. For instance,
+/// `e4`, `Nbd7`, `exd5`, `e8=Q+`, `O-O` or `Qxf7#`.
+///
+/// The moving piece is named by its letter, except for pawns. When several pieces of the same kind
+/// could reach the destination, the origin is named by file, by rank, or else by both. A pawn
+/// capture is named by the pawn's file. A move which gives check is suffixed by `+`, or by `#` if
+/// it delivers checkmate.
+pub(crate) fn to_san(position: &Position, translation: Translation) -> String {
+    let mut text = String::new();
+    let origin = translation.origin();
+    let destination = translation.destination();
+    let kind = position.squares[origin].occupant.unwrap().kind;
+
+    if translation.is_castle() {
+        let (_, file) = split_rwc(destination);
+        text.push_str(if file == 6 { "O-O" } else { "O-O-O" });
+    } else if kind == PieceKind::Pawn {
+        if is_capture(position, translation) {
+            text.push(format_algebraic(origin).chars().next().unwrap());
+            text.push('x');
+        }
+        text.push_str(&format_algebraic(destination));
+        if let Some(promotion) = translation.promotion() {
+            text.push('=');
+            text.push(format_kind(promotion));
+        }
+    } else {
+        text.push(format_kind(kind));
+
+        // Name the origin only as precisely as is needed to distinguish it from the origins of
+        // other pieces of the same kind which could move to the destination.
+        let mut moves = MoveList::new();
+        generate_legal_moves(position, &mut moves);
+        let rivals: Vec<usize> = moves.iter()
+            .filter(|rival| rival.destination() == destination && rival.origin() != origin)
+            .filter(|rival| position.squares[rival.origin()].occupant.unwrap().kind == kind)
+            .map(|rival| rival.origin())
+            .collect();
+        let (rank, file) = split_rwc(origin);
+        let name = format_algebraic(origin);
+        if !rivals.is_empty() {
+            if rivals.iter().all(|&rival| split_rwc(rival).1 != file) {
+                text.push_str(&name[..1]);
+            } else if rivals.iter().all(|&rival| split_rwc(rival).0 != rank) {
+                text.push_str(&name[1..]);
+            } else {
+                text.push_str(&name);
+            }
+        }
+
+        if is_capture(position, translation) { text.push('x'); }
+        text.push_str(&format_algebraic(destination));
+    }
+
+    let mut after = position.clone();
+    make_move(&mut after, translation);
+    if in_check(&after) {
+        let mut replies = MoveList::new();
+        generate_legal_moves(&after, &mut replies);
+        text.push(if replies.is_empty() { '#' } else { '+' });
+    }
+    return text;
+}
+
+/// Finds the legal move of the given [Position] which is written as `text` in Standard Algebraic
+/// Notation. See also, [to_san].
+///
+/// Parsing is lenient. Check and mate suffixes, annotations such as `!?`, capture marks and
+/// `e.p.` are all optional and are not verified. The origin may be named more precisely than
+/// necessary, as in `Nbd7` or `Ng1f3`, and castling may be written with zeros, as in `0-0`.
+/// The `=` before a promoted piece may be omitted. `None` is produced if no legal move matches,
+/// or if the text is ambiguous.
+pub(crate) fn parse_san(position: &Position, text: &str) -> Option<Translation> {
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &mut moves);
+
+    let text = text.trim().trim_end_matches(['+', '#', '!', '?']);
+    let text = text.strip_suffix("e.p.").unwrap_or(text).trim_end();
+
+    let castling = match text {
+        "O-O" | "0-0" => Some(6),
+        "O-O-O" | "0-0-0" => Some(2),
+        _ => None
+    };
+    if let Some(castling_file) = castling {
+        return moves.into_iter()
+            .find(|translation| translation.is_castle() && split_rwc(translation.destination()).1 == castling_file);
+    }
+
+    let mut chars: Vec<char> = text.chars().filter(|&c| c != 'x' && c != '-' && c != ':').collect();
+
+    // The promoted piece follows the destination, with or without an '='.
+    let mut promotion: Option<PieceKind> = None;
+    if chars.len() >= 3 && chars[chars.len() - 2] == '=' {
+        promotion = Some(parse_kind(chars.pop()?.to_ascii_uppercase())?);
+        chars.pop();
+    } else if chars.len() >= 3 && chars[chars.len() - 2].is_ascii_digit() {
+        if let Some(kind) = parse_kind(chars[chars.len() - 1].to_ascii_uppercase()) {
+            promotion = Some(kind);
+            chars.pop();
+        }
+    }
+
+    let kind = match chars.first().and_then(|&c| parse_kind(c)) {
+        Some(kind) => { chars.remove(0); kind },
+        None => PieceKind::Pawn
+    };
+    if chars.len() < 2 { return None; }
+    let destination_name: String = chars[chars.len() - 2..].iter().collect();
+    let destination = parse_algebraic(&destination_name)?;
+
+    // Whatever lies between the piece and the destination names the origin's file, rank, or both.
+    let hint = &chars[..chars.len() - 2];
+    if hint.len() > 2 { return None; }
+    let file_hint = hint.iter().find(|c| c.is_ascii_lowercase()).map(|&c| c as u8);
+    let rank_hint = hint.iter().find(|c| c.is_ascii_digit()).map(|&c| c as u8);
+    if hint.len() != usize::from(file_hint.is_some()) + usize::from(rank_hint.is_some()) { return None; }
+
+    let mut candidates = moves.into_iter().filter(|translation| {
+        let name = format_algebraic(translation.origin()).into_bytes();
+        translation.destination() == destination
+            && translation.promotion() == promotion
+            && position.squares[translation.origin()].occupant.unwrap().kind == kind
+            && file_hint.is_none_or(|file| name[0] == file)
+            && rank_hint.is_none_or(|rank| name[1] == rank)
+    });
+    let found = candidates.next()?;
+    if candidates.next().is_some() { return None; }
+    return Some(found);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Position;
+    use crate::legal::generate_legal_moves;
+    use crate::movegen::MoveList;
+    use crate::notation::parse_coordinates;
+    use crate::san::parse_san;
+    use crate::san::to_san;
+
+    /// Formats the move written in coordinate notation as `coordinates` in Standard Algebraic Notation.
+    fn san(record: &str, coordinates: &str) -> String {
+        let position = Position::from_fen(record).unwrap();
+        return to_san(&position, parse_coordinates(&position, coordinates).unwrap());
+    }
+
+    /// Parses `text` as Standard Algebraic Notation, producing the move in coordinate notation.
+    fn parse(record: &str, text: &str) -> Option<String> {
+        let position = Position::from_fen(record).unwrap();
+        return parse_san(&position, text).map(|translation| translation.to_string());
+    }
+
+    #[test]
+    fn disambiguation() {
+        let rooks = "4k3/8/8/R7/8/8/8/R4RK1 w - - 0 1";
+        assert_eq!(san(rooks, "a1d1"), "Rad1");
+        assert_eq!(san(rooks, "f1d1"), "Rfd1");
+        assert_eq!(san(rooks, "a1a3"), "R1a3");
+        assert_eq!(san(rooks, "a5a3"), "R5a3");
+        assert_eq!(san(rooks, "a1b1"), "Rab1");
+        assert_eq!(san(rooks, "a5b5"), "Rb5");
+        assert_eq!(parse(rooks, "Rad1").as_deref(), Some("a1d1"));
+        assert_eq!(parse(rooks, "R1a3").as_deref(), Some("a1a3"));
+
+        let queens = "8/8/k7/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(queens, "h4e1"), "Qh4e1");
+        assert_eq!(san(queens, "e4e1"), "Qee1");
+        assert_eq!(san(queens, "h1e1"), "Q1e1");
+        assert_eq!(parse(queens, "Qh4e1").as_deref(), Some("h4e1"));
+    }
+
+    #[test]
+    fn ambiguity() {
+        let rooks = "4k3/8/8/R7/8/8/8/R4RK1 w - - 0 1";
+        assert_eq!(parse(rooks, "Rd1"), None);
+        assert_eq!(parse(rooks, "Ra3"), None);
+        let queens = "8/8/k7/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(parse(queens, "Qhe1"), None);
+        assert_eq!(parse(queens, "Q4e1"), None);
+    }
+
+    #[test]
+    fn promotions() {
+        let record = "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(record, "a7a8q"), "a8=Q");
+        assert_eq!(san(record, "a7b8n"), "axb8=N");
+        assert_eq!(parse(record, "a8=Q").as_deref(), Some("a7a8q"));
+        assert_eq!(parse(record, "a8Q").as_deref(), Some("a7a8q"));
+        assert_eq!(parse(record, "a8=R+").as_deref(), Some("a7a8r"));
+        assert_eq!(parse(record, "axb8=N").as_deref(), Some("a7b8n"));
+        assert_eq!(parse(record, "axb8B").as_deref(), Some("a7b8b"));
+        assert_eq!(parse(record, "a8"), None);
+    }
+
+    #[test]
+    fn castling() {
+        let record = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(record, "e1g1"), "O-O");
+        assert_eq!(san(record, "e1c1"), "O-O-O");
+        assert_eq!(parse(record, "O-O").as_deref(), Some("e1g1"));
+        assert_eq!(parse(record, "0-0").as_deref(), Some("e1g1"));
+        assert_eq!(parse(record, "0-0-0").as_deref(), Some("e1c1"));
+        assert_eq!(parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1", "O-O"), None);
+    }
+
+    #[test]
+    fn pawn_captures() {
+        let record = "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1";
+        assert_eq!(san(record, "e4d5"), "exd5");
+        assert_eq!(parse(record, "exd5").as_deref(), Some("e4d5"));
+        assert_eq!(parse(record, "ed5").as_deref(), Some("e4d5"));
+
+        let record = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san(record, "e5d6"), "exd6");
+        assert_eq!(parse(record, "exd6 e.p.").as_deref(), Some("e5d6"));
+    }
+
+    #[test]
+    fn check_and_mate() {
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+        assert_eq!(san("6k1/5pp1/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(parse("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8#").as_deref(), Some("a1a8"));
+        assert_eq!(parse("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8!?").as_deref(), Some("a1a8"));
+    }
+
+    #[test]
+    fn round_trip() {
+        for record in ["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                       "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                       "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                       "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                       "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                       "8/8/k7/8/4Q2Q/8/8/K6Q w - - 0 1"] {
+            let position = Position::from_fen(record).unwrap();
+            let mut moves = MoveList::new();
+            generate_legal_moves(&position, &mut moves);
+            for translation in moves {
+                let text = to_san(&position, translation);
+                assert_eq!(parse_san(&position, &text), Some(translation), "{} in {}", text, record);
+            }
+        }
+    }
+}
@@ -0,0 +1,649 @@
+use std::cmp::Reverse;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::LazyLock;
+use std::time::Duration;
+use std::time::Instant;
+use crate::PieceKind;
+use crate::Position;
+use crate::select_pieces;
+use crate::Translation;
+use crate::draw::DrawReason;
+use crate::draw::lookup_draw;
+use crate::eval::evaluate;
+use crate::eval::PIECE_VALUES;
+use crate::legal::generate_legal_captures;
+use crate::legal::generate_legal_moves;
+use crate::legal::in_check;
+use crate::makemove::make_move;
+use crate::makemove::make_null_move;
+use crate::makemove::unmake_move;
+use crate::makemove::unmake_null_move;
+use crate::movegen::MoveList;
+use crate::movepick::estimate_gain;
+use crate::movepick::History;
+use crate::movepick::is_quiet;
+use crate::movepick::MovePicker;
+use crate::movepick::mvv_lva;
+use crate::movepick::PriorMove;
+use crate::see::see;
+use crate::tt::Bound;
+use crate::timeman::Deadlines;
+use crate::timeman::TimeManager;
+use crate::tt::TranspositionTable;
+
+/// A quantity describing the desirability of a position, in centipawns, from the perspective of
+/// the color to move. Scores near [MATE] describe forced checkmates.
+pub(crate) type Score = i32;
+
+/// The score of delivering checkmate immediately. Checkmate `n` plies in the future scores `MATE - n`.
+pub(crate) const MATE: Score = 30000;
+
+/// A bound exceeding every attainable [Score].
+pub(crate) const INFINITY: Score = 31000;
+
+/// The greatest distance, in plies, from the root at which the search will examine a position.
+pub(crate) const MAX_PLY: usize = 96;
+
+pub(crate) fn is_mate_score(score: Score) -> bool {
+    return score.abs() >= MATE - MAX_PLY as Score;
+}
+
+/// Bounds the effort spent by [search]. The search ends upon reaching any one of the limits.
+/// An unbounded search ends only when it is stopped.
+#[derive(Copy, Clone, Default, Debug)]
+pub(crate) struct SearchLimits {
+    /// The greatest depth, in plies, to which the root is searched.
+    pub(crate) depth: Option<u32>,
+    pub(crate) nodes: Option<u64>,
+    pub(crate) time: Option<Deadlines>
+}
+
+/// Switches for the optional features of [search].
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct SearchOptions {
+    /// The number of threads which search the position together. See [search].
+    pub(crate) threads: usize,
+
+    /// Whether quiescence search answers a check at its first ply by searching every evasion,
+    /// rather than standing pat or searching only captures.
+    pub(crate) quiescence_evasions: bool,
+
+    /// Whether to skip a node when passing the turn to the opponent still fails high.
+    pub(crate) null_move_pruning: bool,
+
+    /// Whether to search later moves of a node to reduced depth, per [LMR_TABLE].
+    pub(crate) late_move_reductions: bool,
+
+    /// Whether to skip quiet moves near the horizon when the static evaluation is far below alpha.
+    pub(crate) futility_pruning: bool,
+
+    /// Whether to skip a node near the horizon when the static evaluation is far above beta.
+    pub(crate) reverse_futility_pruning: bool,
+
+    /// Whether to resolve a node near the horizon by quiescence search, when the static evaluation
+    /// is far below alpha.
+    pub(crate) razoring: bool,
+
+    /// Whether to extend the table's move, when every alternative is much worse.
+    pub(crate) singular_extensions: bool,
+
+    /// Whether to extend moves which give check.
+    pub(crate) check_extensions: bool
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        return SearchOptions {
+            threads: 1,
+            quiescence_evasions: true,
+            null_move_pruning: true,
+            late_move_reductions: true,
+            futility_pruning: true,
+            reverse_futility_pruning: true,
+            razoring: true,
+            singular_extensions: true,
+            check_extensions: true
+        };
+    }
+}
+
+/// Describes the outcome of the deepest iteration which [search] completed.
+#[derive(Clone, Debug)]
+pub(crate) struct SearchReport {
+    /// The best move found. This is `None` only if the root has no legal moves.
+    pub(crate) best: Option<Translation>,
+    pub(crate) score: Score,
+    pub(crate) depth: u32,
+
+    /// The principal variation. That is, the sequence of moves expected to be played, beginning with `best`.
+    pub(crate) pv: Vec<Translation>,
+    pub(crate) nodes: u64,
+    pub(crate) elapsed: Duration,
+
+    /// The occupancy of the [TranspositionTable], in permille.
+    pub(crate) hashfull: u32
+}
+
+/// The number of nodes between consecutive checks of the stop flag and the clock.
+const ABORT_CHECK_INTERVAL: u64 = 1024;
+
+/// The reduction, in plies, of the `m`th move searched at depth `d`, at index `[d][m]`. Later moves,
+/// and moves at greater depth, are reduced further.
+static LMR_TABLE: LazyLock<[[u32; 64]; MAX_PLY]> = LazyLock::new(|| {
+    let mut table = [[0; 64]; MAX_PLY];
+    for (depth, row) in table.iter_mut().enumerate().skip(1) {
+        for (index, reduction) in row.iter_mut().enumerate().skip(1) {
+            *reduction = (0.75 + (depth as f64).ln() * (index as f64).ln() / 2.25) as u32;
+        }
+    }
+    return table;
+});
+
+/// Reverse futility pruning applies up to this depth, with a margin of this many centipawns per ply.
+const REVERSE_FUTILITY_DEPTH: u32 = 6;
+const REVERSE_FUTILITY_MARGIN: Score = 80;
+
+/// Futility pruning applies up to this depth, with the margin given for each depth.
+const FUTILITY_DEPTH: u32 = 3;
+const FUTILITY_MARGINS: [Score; FUTILITY_DEPTH as usize + 1] = [0, 150, 270, 390];
+
+/// Razoring applies up to this depth, with the margin given for each depth.
+const RAZORING_DEPTH: u32 = 2;
+const RAZORING_MARGINS: [Score; RAZORING_DEPTH as usize + 1] = [0, 300, 450];
+
+/// Null-move pruning applies from this depth.
+const NULL_MOVE_DEPTH: u32 = 3;
+
+/// Singular extensions apply from this depth, to moves whose table entry is at most this many plies
+/// shallower than the node.
+const SINGULAR_DEPTH: u32 = 8;
+const SINGULAR_DEPTH_SLACK: u32 = 3;
+
+/// The margin, in centipawns, by which a capture may improve upon its material gain, say by
+/// positional effects, before delta pruning discards it.
+const DELTA_MARGIN: Score = 200;
+
+struct Searcher<'a> {
+    limits: SearchLimits,
+    options: SearchOptions,
+    tt: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+
+    /// The nodes searched by every thread, which each thread adds to periodically.
+    shared_nodes: &'a AtomicU64,
+
+    /// Whether the current iteration was cut short by a limit or the stop flag.
+    aborted: bool,
+
+    /// The triangular principal-variation table. Row `ply` holds the best line found from that ply.
+    pv: Vec<MoveList>,
+
+    /// The principal variation of the previous iteration, whose moves are searched first, unless the
+    /// [TranspositionTable] suggests another.
+    prior_pv: MoveList,
+
+    history: History,
+
+    /// The move made at each ply of the line currently being searched. A null move is recorded as `None`.
+    line: [Option<PriorMove>; MAX_PLY],
+
+    /// The move to be skipped at each ply, while verifying that the table's move is singular.
+    excluded: [Option<Translation>; MAX_PLY],
+
+    /// The nodes spent on each move of the root, during the current iteration.
+    root_effort: Vec<(Translation, u64)>
+}
+
+impl<'a> Searcher<'a> {
+    fn new(limits: SearchLimits, options: SearchOptions, tt: &'a TranspositionTable, stop: &'a AtomicBool,
+           shared_nodes: &'a AtomicU64) -> Self {
+        return Searcher {
+            limits,
+            options,
+            tt,
+            stop,
+            start: Instant::now(),
+            nodes: 0,
+            shared_nodes,
+            aborted: false,
+            pv: (0..MAX_PLY).map(|_| MoveList::with_capacity(MAX_PLY)).collect(),
+            prior_pv: MoveList::new(),
+            history: History::new(),
+            line: [None; MAX_PLY],
+            excluded: [None; MAX_PLY],
+            root_effort: Vec::new()
+        };
+    }
+
+    fn check_abort(&mut self) {
+        // The node limit is of the nodes searched by every thread, as reported.
+        if self.limits.nodes.is_some_and(|nodes| self.count_nodes() >= nodes) { self.aborted = true; }
+        if !self.nodes.is_multiple_of(ABORT_CHECK_INTERVAL) { return; }
+        self.shared_nodes.fetch_add(ABORT_CHECK_INTERVAL, Ordering::Relaxed);
+        if self.stop.load(Ordering::Relaxed) { self.aborted = true; }
+        if self.limits.time.is_some_and(|time| self.start.elapsed() >= time.hard) { self.aborted = true; }
+    }
+
+    /// Counts the nodes searched by every thread, including those this thread has yet to share.
+    fn count_nodes(&self) -> u64 {
+        return self.shared_nodes.load(Ordering::Relaxed) + self.nodes % ABORT_CHECK_INTERVAL;
+    }
+
+    /// Records that `translation` followed by the principal variation of the next ply is the best
+    /// line found from `ply`.
+    fn update_pv(&mut self, ply: usize, translation: Translation) {
+        let (head, tail) = self.pv.split_at_mut(ply + 1);
+        let line = &mut head[ply];
+        line.clear();
+        Vec::push(line, translation);
+        line.extend_from_slice(&tail[0]);
+    }
+
+    /// Searches the given position to the given depth using the principal-variation variant of
+    /// negamax with alpha-beta pruning. The score is exact if it falls within (alpha, beta).
+    ///
+    /// Outside of the principal variation, a search of sufficient depth recorded in the
+    /// [TranspositionTable] substitutes for searching the position again. There, too, the search
+    /// may prune or reduce unpromising nodes and moves, and extend promising ones, as enabled by
+    /// [SearchOptions].
+    fn pvs(&mut self, position: &mut Position, depth: u32, ply: usize, mut alpha: Score, beta: Score) -> Score {
+        self.pv[ply].clear();
+        if depth == 0 { return self.quiesce(position, ply, alpha, beta, true); }
+        self.nodes += 1;
+        self.check_abort();
+        if self.aborted { return 0; }
+
+        if ply >= MAX_PLY - 1 { return evaluate(position); }
+
+        // Within the search, a single repetition is as good as a draw. The fifty-move rule yields to
+        // checkmate, though, so a King checked on the hundredth halfmove must be able to escape.
+        if ply > 0 {
+            match lookup_draw(position, 2) {
+                Some(DrawReason::FiftyMoves) if in_check(position) => {
+                    let mut moves = MoveList::new();
+                    generate_legal_moves(position, &mut moves);
+                    if moves.is_empty() { return -MATE + ply as Score; }
+                    return 0;
+                },
+                Some(_) => return 0,
+                None => {}
+            }
+        }
+
+        let original_alpha = alpha;
+        let pv_node = beta - alpha > 1;
+        let excluded = self.excluded[ply];
+        let entry = match excluded {
+            Some(_) => None,
+            None => self.tt.probe(position.key, ply)
+        };
+        if let Some(entry) = entry.filter(|entry| ply > 0 && !pv_node && entry.depth >= depth) {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha
+            };
+            if cutoff { return entry.score; }
+        }
+
+        let checked = in_check(position);
+        let static_eval = if checked { -INFINITY } else { evaluate(position) };
+        let options = self.options;
+
+        if !pv_node && !checked && excluded.is_none() {
+            if options.reverse_futility_pruning && depth <= REVERSE_FUTILITY_DEPTH && !is_mate_score(beta)
+                && static_eval - REVERSE_FUTILITY_MARGIN * depth as Score >= beta {
+                return static_eval;
+            }
+
+            if options.razoring && depth <= RAZORING_DEPTH
+                && static_eval + RAZORING_MARGINS[depth as usize] < alpha {
+                let score = self.quiesce(position, ply, alpha, beta, true);
+                if depth == 1 || score < alpha { return score; }
+            }
+
+            // Pass the turn. Should the opponent, moving twice, still fail to bring the score below
+            // beta, then the node is surely good enough. This fails in zugzwang, where any move
+            // would worsen the position. So, it is not tried in endgames of only pawns, where
+            // zugzwang is common, nor twice in a row.
+            if options.null_move_pruning && depth >= NULL_MOVE_DEPTH && static_eval >= beta
+                && ply > 0 && self.line[ply - 1].is_some() && has_non_pawn_material(position) {
+                let reduction = 3 + depth / 6;
+                let undo = make_null_move(position);
+                self.line[ply] = None;
+                let score = -self.pvs(position, depth.saturating_sub(1 + reduction), ply + 1, -beta, -beta + 1);
+                unmake_null_move(position, undo);
+                if self.aborted { return 0; }
+                if score >= beta { return if is_mate_score(score) { beta } else { score }; }
+            }
+        }
+
+        // Should every alternative to the table's move fall well short of its score, the move is
+        // singular, and worth searching more deeply.
+        let mut singular_move: Option<Translation> = None;
+        if let Some(entry) = entry {
+            if options.singular_extensions && ply > 0 && depth >= SINGULAR_DEPTH && entry.best.is_some()
+                && entry.bound != Bound::Upper && entry.depth + SINGULAR_DEPTH_SLACK >= depth
+                && !is_mate_score(entry.score) {
+                let singular_beta = entry.score - 2 * depth as Score;
+                self.excluded[ply] = entry.best;
+                let score = self.pvs(position, (depth - 1) / 2, ply, singular_beta - 1, singular_beta);
+                self.excluded[ply] = None;
+                if self.aborted { return 0; }
+                if score < singular_beta { singular_move = entry.best; }
+            }
+        }
+
+        // Search the best move recorded in the table first, or else that of the previous
+        // iteration's principal variation.
+        let hint = entry.and_then(|entry| entry.best).or_else(|| self.prior_pv.get(ply).copied());
+        let prior = if ply > 0 { self.line[ply - 1] } else { None };
+        let mut picker = MovePicker::new(hint, self.history.killers(ply), self.history.countermove(prior));
+
+        let mut best_score = -INFINITY;
+        let mut best_move: Option<Translation> = None;
+        let mut searched = 0;
+        let mut quiets_tried = MoveList::new();
+        while let Some(translation) = picker.next(position, &self.history, prior) {
+            if Some(translation) == excluded { continue; }
+            let quiet = is_quiet(position, translation);
+            let piece = position.squares[translation.origin()].occupant.unwrap();
+            let undo = make_move(position, translation);
+            let gives_check = in_check(position);
+
+            // Skip quiet moves which cannot plausibly raise the score to alpha.
+            if options.futility_pruning && !pv_node && !checked && !gives_check && quiet && searched > 0
+                && depth <= FUTILITY_DEPTH && !is_mate_score(alpha)
+                && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha {
+                unmake_move(position, undo);
+                continue;
+            }
+
+            self.line[ply] = Some(PriorMove { piece, destination: translation.destination() });
+            let mut extension = 0;
+            if options.check_extensions && gives_check { extension = 1; }
+            if Some(translation) == singular_move { extension = 1; }
+            if ply >= MAX_PLY / 2 { extension = 0; }
+            let new_depth = depth - 1 + extension;
+
+            let nodes_before = self.nodes;
+            let mut score: Score;
+            if searched == 0 {
+                score = -self.pvs(position, new_depth, ply + 1, -beta, -alpha);
+            } else {
+                // Search moves late in the ordering to reduced depth, with a null window. Should one
+                // prove better than expected, search it again at full depth.
+                let mut reduction = 0;
+                if options.late_move_reductions && depth >= 3 && quiet && !checked && !gives_check {
+                    reduction = LMR_TABLE[depth as usize][searched.min(63)];
+                    if pv_node { reduction = reduction.saturating_sub(1); }
+                    reduction = reduction.min(new_depth - 1);
+                }
+
+                // Prove that the move is no better than the best so far, using a null window.
+                // Should that fail, search it again with the full window.
+                score = -self.pvs(position, new_depth - reduction, ply + 1, -alpha - 1, -alpha);
+                if reduction > 0 && score > alpha {
+                    score = -self.pvs(position, new_depth, ply + 1, -alpha - 1, -alpha);
+                }
+                if score > alpha && score < beta {
+                    score = -self.pvs(position, new_depth, ply + 1, -beta, -alpha);
+                }
+            }
+            unmake_move(position, undo);
+            if self.aborted { return 0; }
+            searched += 1;
+            if ply == 0 { Vec::push(&mut self.root_effort, (translation, self.nodes - nodes_before)); }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(translation);
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, translation);
+                    if alpha >= beta {
+                        if quiet { self.history.reward(position, prior, ply, translation, depth, &quiets_tried); }
+                        break;
+                    }
+                }
+            }
+            if quiet { Vec::push(&mut quiets_tried, translation); }
+        }
+
+        if searched == 0 {
+            // The only legal move was excluded.
+            if excluded.is_some() { return alpha; }
+            return match checked {
+                true => -MATE + ply as Score,
+                false => 0
+            };
+        }
+
+        let bound = if best_score >= beta { Bound::Lower }
+            else if best_score > original_alpha { Bound::Exact }
+            else { Bound::Upper };
+        if excluded.is_none() { self.tt.store(position.key, ply, best_move, depth, bound, best_score); }
+        return best_score;
+    }
+
+    /// Searches only the captures and promotions of the given position, and those which follow them,
+    /// until the position is quiet. This mitigates the horizon effect, whereby the main search
+    /// misjudges a position in the midst of an exchange.
+    ///
+    /// The color to move may decline every capture and "stand pat", accepting the static evaluation.
+    /// At the `first` ply, while in check, every evasion is searched instead, if enabled by
+    /// [SearchOptions::quiescence_evasions].
+    fn quiesce(&mut self, position: &mut Position, ply: usize, mut alpha: Score, beta: Score, first: bool) -> Score {
+        self.pv[ply].clear();
+        self.nodes += 1;
+        self.check_abort();
+        if self.aborted { return 0; }
+        if ply >= MAX_PLY - 1 { return evaluate(position); }
+
+        let mut moves = MoveList::new();
+        let evading = first && self.options.quiescence_evasions && in_check(position);
+        let mut best_score: Score;
+        let stand_pat: Score;
+        if evading {
+            generate_legal_moves(position, &mut moves);
+            if moves.is_empty() { return -MATE + ply as Score; }
+            stand_pat = -INFINITY;
+            best_score = -INFINITY;
+        } else {
+            stand_pat = evaluate(position);
+            if stand_pat >= beta { return stand_pat; }
+
+            // Even capturing a queen would not raise the score to alpha.
+            if stand_pat + PIECE_VALUES[PieceKind::Queen as usize] + DELTA_MARGIN < alpha { return stand_pat; }
+
+            alpha = alpha.max(stand_pat);
+            best_score = stand_pat;
+            generate_legal_captures(position, &mut moves);
+        }
+        moves.sort_by_cached_key(|&translation| Reverse(mvv_lva(position, translation)));
+
+        for translation in moves {
+            // Delta pruning. Skip captures which cannot raise the score to alpha.
+            if !evading && stand_pat + estimate_gain(position, translation) + DELTA_MARGIN <= alpha { continue; }
+
+            // Skip captures which lose material once the opponent recaptures.
+            if !evading && see(position, translation) < 0 { continue; }
+
+            let undo = make_move(position, translation);
+            let score = -self.quiesce(position, ply + 1, -beta, -alpha, false);
+            unmake_move(position, undo);
+            if self.aborted { return 0; }
+
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, translation);
+                    if alpha >= beta { break; }
+                }
+            }
+        }
+        return best_score;
+    }
+}
+
+/// Determines whether the color to move has any pieces besides pawns and its King.
+fn has_non_pawn_material(position: &Position) -> bool {
+    return [PieceKind::Rook, PieceKind::Knight, PieceKind::Bishop, PieceKind::Queen].into_iter()
+        .any(|kind| select_pieces(position, position.ctm, kind) > 0);
+}
+
+/// Staggers the iterations of helper threads, so that they do not all search the same depth at once.
+/// Helper `i` skips depth `d` if `(d + SKIP_PHASE[i]) / SKIP_SIZE[i]` is odd, indices taken modulo 20.
+const SKIP_SIZE: [u32; 20] = [1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4];
+const SKIP_PHASE: [u32; 20] = [0, 1, 0, 1, 2, 3, 0, 1, 2, 3, 4, 5, 0, 1, 2, 3, 4, 5, 6, 7];
+
+/// Searches alongside the main thread until `finished` is raised, by iterative deepening, skipping
+/// some depths. Its results reach the main thread only through the [TranspositionTable].
+fn help(id: usize, mut position: Position, options: SearchOptions, tt: &TranspositionTable, finished: &AtomicBool,
+        shared_nodes: &AtomicU64) {
+    let mut helper = Searcher::new(SearchLimits::default(), options, tt, finished, shared_nodes);
+    let i = (id - 1) % SKIP_SIZE.len();
+    for depth in 1..MAX_PLY as u32 {
+        if ((depth + SKIP_PHASE[i]) / SKIP_SIZE[i]) % 2 == 1 { continue; }
+        helper.pvs(&mut position, depth, 0, -INFINITY, INFINITY);
+        if helper.aborted { break; }
+        helper.prior_pv = helper.pv[0].clone();
+    }
+    shared_nodes.fetch_add(helper.nodes % ABORT_CHECK_INTERVAL, Ordering::Relaxed);
+}
+
+/// Searches the given [Position] by iterative deepening, until one of the `limits` is reached or
+/// `stop` is raised. The search may be tuned by the given [SearchOptions], and draws upon and adds to
+/// the given [TranspositionTable]. After each completed iteration, its outcome is passed to `report`.
+///
+/// When more than one thread is requested, the remainder search the position as helpers, sharing
+/// only the table (Lazy SMP). The calling thread remains the main thread, which alone observes the
+/// limits, reports, and chooses the best move. The helpers are stopped once it finishes.
+///
+/// The report of the deepest completed iteration is returned. If not even the first iteration
+/// completes, the first legal move is returned as the best.
+pub(crate) fn search(position: &mut Position, limits: SearchLimits, options: SearchOptions,
+                     tt: &TranspositionTable, stop: &AtomicBool, mut report: impl FnMut(&SearchReport)) -> SearchReport {
+    tt.new_search();
+    let shared_nodes = AtomicU64::new(0);
+    let finished = AtomicBool::new(false);
+
+    let mut root_moves = MoveList::new();
+    generate_legal_moves(position, &mut root_moves);
+    let mut outcome = SearchReport {
+        best: root_moves.first().copied(),
+        score: 0,
+        depth: 0,
+        pv: MoveList::new(),
+        nodes: 0,
+        elapsed: Duration::ZERO,
+        hashfull: 0
+    };
+    if root_moves.is_empty() { return outcome; }
+
+    let mut searcher = Searcher::new(limits, options, tt, stop, &shared_nodes);
+    std::thread::scope(|scope| {
+        for id in 1..options.threads {
+            let position = position.clone();
+            let (finished, shared_nodes) = (&finished, &shared_nodes);
+            scope.spawn(move || help(id, position, options, tt, finished, shared_nodes));
+        }
+
+        let mut time_manager = limits.time.map(TimeManager::new);
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32 - 1).min(MAX_PLY as u32 - 1);
+        for depth in 1..=max_depth {
+            searcher.root_effort.clear();
+            let score = searcher.pvs(position, depth, 0, -INFINITY, INFINITY);
+            if searcher.aborted { break; }
+
+            searcher.prior_pv = searcher.pv[0].clone();
+            outcome = SearchReport {
+                best: searcher.pv[0].first().copied(),
+                score,
+                depth,
+                pv: searcher.pv[0].clone(),
+                nodes: searcher.count_nodes(),
+                elapsed: searcher.start.elapsed(),
+                hashfull: tt.hashfull()
+            };
+            report(&outcome);
+
+            if let Some(time_manager) = &mut time_manager {
+                let total: u64 = searcher.root_effort.iter().map(|&(_, nodes)| nodes).sum();
+                let spent = searcher.root_effort.iter()
+                    .find(|&&(translation, _)| Some(translation) == outcome.best)
+                    .map_or(0, |&(_, nodes)| nodes);
+                let effort = spent as f64 / total.max(1) as f64;
+                if time_manager.should_stop(outcome.elapsed, outcome.best, score, effort) { break; }
+            }
+        }
+        finished.store(true, Ordering::Relaxed);
+    });
+    outcome.nodes = searcher.count_nodes();
+    outcome.elapsed = searcher.start.elapsed();
+    return outcome;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use crate::Position;
+    use crate::legal::generate_legal_moves;
+    use crate::makemove::make_move;
+    use crate::movegen::MoveList;
+    use crate::search::MATE;
+    use crate::search::search;
+    use crate::search::SearchLimits;
+    use crate::search::SearchOptions;
+    use crate::search::SearchReport;
+    use crate::tt::TranspositionTable;
+
+    fn search_to_depth(record: &str, depth: u32) -> SearchReport {
+        let mut position = Position::from_fen(record).unwrap();
+        let limits = SearchLimits { depth: Some(depth), ..SearchLimits::default() };
+        let tt = TranspositionTable::new(1);
+        return search(&mut position, limits, SearchOptions::default(), &tt, &AtomicBool::new(false), |_| {});
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let report = search_to_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!(report.score, MATE - 1);
+        assert_eq!(report.pv.len(), 1);
+    }
+
+    #[test]
+    fn mate_in_two() {
+        // After Ra7, the King is confined to the back rank, and Rb8 mates.
+        let report = search_to_depth("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 4);
+        assert_eq!(report.score, MATE - 3);
+    }
+
+    #[test]
+    fn principal_variation_is_legal() {
+        let record = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let report = search_to_depth(record, 4);
+        assert_eq!(report.depth, 4);
+        assert_eq!(report.best, report.pv.first().copied());
+        let mut position = Position::from_fen(record).unwrap();
+        for &translation in &report.pv {
+            let mut moves = MoveList::new();
+            generate_legal_moves(&position, &mut moves);
+            assert!(moves.contains(&translation));
+            make_move(&mut position, translation);
+        }
+    }
+
+    #[test]
+    fn checkmate_outranks_fifty_moves() {
+        // Ra8# is played on the hundredth halfmove without a capture or pawn advance.
+        let report = search_to_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80", 3);
+        assert_eq!(report.score, MATE - 1);
+        assert_eq!(report.best.map(|best| best.to_string()).as_deref(), Some("a1a8"));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::clear;
+use crate::layout;
+use crate::opponent;
+use crate::PieceKind;
+use crate::Position;
+use crate::select_color;
+use crate::Translation;
+use crate::attacks::attackers_to;
+use crate::eval::PIECE_VALUES;
+use crate::locate::BoardLayout;
+use crate::movegen::locate_en_passant_victim;
+use crate::search::Score;
+
+/// The kinds of piece in the order in which they are sent into an exchange, least valuable first.
+const EXCHANGE_ORDER: [PieceKind; 6] = [PieceKind::Pawn, PieceKind::Knight, PieceKind::Bishop,
+    PieceKind::Rook, PieceKind::Queen, PieceKind::King];
+
+/// The value of the King in an exchange. It exceeds the value of every other piece combined, so
+/// the King never captures onto a square which the opponent still attacks.
+const KING_EXCHANGE_VALUE: Score = 20000;
+
+fn exchange_value(kind: PieceKind) -> Score {
+    return match kind {
+        PieceKind::King => KING_EXCHANGE_VALUE,
+        _ => PIECE_VALUES[kind as usize]
+    }
+}
+
+/// The greatest number of captures which can occur on a single square, one per piece.
+const MAX_EXCHANGE_LENGTH: usize = 32;
+
+/// Statically evaluates the exchange begun by the given capture. That is, the material gained, in
+/// centipawns, by the color to move, once both colors have recaptured on the destination square
+/// with their least valuable attacker for as long as doing so is profitable. Either color may
+/// stop recapturing at any point.
+///
+/// Attackers are found by [attackers_to], recomputed after each capture with the capturing piece's
+/// origin cleared from the [CompositeOccupancy], so that sliding pieces lined up behind one another
+/// (x-rays) join the exchange in turn. Pins are disregarded.
+///
+/// [CompositeOccupancy]: crate::CompositeOccupancy
+pub(crate) fn see(position: &Position, translation: Translation) -> Score {
+    if translation.is_castle() { return 0; }
+    let target = translation.destination();
+    let mut board = position.occupancy;
+    let mover = position.squares[translation.origin()].occupant.unwrap();
+
+    // The material won by the capture which ends each ply of the exchange, from the perspective
+    // of the color making it.
+    let mut gains: [Score; MAX_EXCHANGE_LENGTH] = [0; MAX_EXCHANGE_LENGTH];
+    gains[0] = match translation.is_en_passant() {
+        true => {
+            clear(&mut board, opponent(mover.color), locate_en_passant_victim(target, mover.color));
+            PIECE_VALUES[PieceKind::Pawn as usize]
+        },
+        false => position.squares[target].occupant.map_or(0, |piece| exchange_value(piece.kind))
+    };
+    let mut occupant_value = exchange_value(mover.kind);
+    if let Some(kind) = translation.promotion() {
+        gains[0] += PIECE_VALUES[kind as usize] - PIECE_VALUES[PieceKind::Pawn as usize];
+        occupant_value = PIECE_VALUES[kind as usize];
+    }
+    clear(&mut board, mover.color, translation.origin());
+
+    let mut color = opponent(mover.color);
+    let mut depth = 0;
+    while depth + 1 < MAX_EXCHANGE_LENGTH {
+        let attackers = attackers_to(position, target, &board)
+            & select_color(layout(&board, BoardLayout::Rankwise), color);
+        let Some(kind) = EXCHANGE_ORDER.into_iter()
+            .find(|&kind| attackers & position.kinds[kind as usize] > 0) else { break; };
+        let attacker = (attackers & position.kinds[kind as usize]).trailing_zeros() as usize;
+
+        depth += 1;
+        gains[depth] = occupant_value - gains[depth - 1];
+        occupant_value = exchange_value(kind);
+        clear(&mut board, color, attacker);
+        color = opponent(color);
+    }
+
+    // Each color recaptures only if doing so is better than stopping.
+    while depth > 0 {
+        gains[depth - 1] = -Score::max(-gains[depth - 1], gains[depth]);
+        depth -= 1;
+    }
+    return gains[0];
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Position;
+    use crate::notation::parse_coordinates;
+    use crate::search::Score;
+    use crate::see::see;
+
+    fn see_of(record: &str, coordinates: &str) -> Score {
+        let position = Position::from_fen(record).unwrap();
+        return see(&position, parse_coordinates(&position, coordinates).unwrap());
+    }
+
+    #[test]
+    fn undefended_capture() {
+        assert_eq!(see_of("4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1", "d1d5"), 320);
+    }
+
+    #[test]
+    fn defended_capture() {
+        assert_eq!(see_of("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", "d1d5"), 100 - 900);
+    }
+
+    #[test]
+    fn x_ray() {
+        // The second Rook recaptures through the first, so winning the pawn is safe.
+        assert_eq!(see_of("4r1k1/8/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5"), 100);
+        assert_eq!(see_of("4r1k1/8/8/4p3/8/8/4R3/6K1 w - - 0 1", "e2e5"), 100 - 500);
+        // Likewise with a Queen behind, while a Queen in front is lost to the Rook.
+        assert_eq!(see_of("4r1k1/8/8/4p3/8/8/4R3/4Q1K1 w - - 0 1", "e2e5"), 100);
+        assert_eq!(see_of("4r1k1/8/8/4p3/8/8/4Q3/4R1K1 w - - 0 1", "e2e5"), 100 - 900 + 500);
+    }
+
+    #[test]
+    fn en_passant() {
+        assert_eq!(see_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+        assert_eq!(see_of("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 0);
+    }
+
+    #[test]
+    fn promotion() {
+        assert_eq!(see_of("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q"), 320 + 900 - 100);
+        assert_eq!(see_of("rn2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q"), 320 + 900 - 100 - 900);
+    }
+}
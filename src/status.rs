@@ -0,0 +1,117 @@
+use crate::opponent;
+use crate::PieceColor;
+use crate::Position;
+use crate::draw::DrawReason;
+use crate::draw::lookup_draw;
+use crate::legal::generate_legal_moves;
+use crate::legal::in_check;
+use crate::movegen::MoveList;
+
+/// The state of a game, as of some [Position]. See [Position::status].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum GameStatus {
+    Ongoing,
+
+    /// The color to move is in check and has no legal moves. The given color, its opponent, has won.
+    Checkmate(PieceColor),
+
+    /// The color to move is not in check, but has no legal moves. The game is drawn.
+    Stalemate,
+
+    Draw(DrawReason)
+}
+
+impl Position {
+    /// Determines whether the game has ended in this position, and how.
+    ///
+    /// Checkmate takes precedence over every draw, so a move which delivers checkmate wins even as
+    /// it completes fifty moves without a capture. Repetition counts as a draw on the third
+    /// occurrence of a position, considering only the history recorded by [make_move].
+    ///
+    /// [make_move]: crate::makemove::make_move
+    pub(crate) fn status(&self) -> GameStatus {
+        let mut moves = MoveList::new();
+        generate_legal_moves(self, &mut moves);
+        if moves.is_empty() {
+            return match in_check(self) {
+                true => GameStatus::Checkmate(opponent(self.ctm)),
+                false => GameStatus::Stalemate
+            };
+        }
+        return match lookup_draw(self, 3) {
+            Some(reason) => GameStatus::Draw(reason),
+            None => GameStatus::Ongoing
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PieceColor;
+    use crate::Position;
+    use crate::draw::DrawReason;
+    use crate::fen::STARTING_FEN;
+    use crate::makemove::make_move;
+    use crate::notation::parse_coordinates;
+    use crate::status::GameStatus;
+
+    fn play(position: &mut Position, moves: &[&str]) {
+        for &text in moves {
+            let translation = parse_coordinates(position, text).unwrap();
+            make_move(position, translation);
+        }
+    }
+
+    fn status(record: &str) -> GameStatus { return Position::from_fen(record).unwrap().status(); }
+
+    #[test]
+    fn ongoing() {
+        assert_eq!(status(STARTING_FEN), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn checkmate() {
+        assert_eq!(status("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+            GameStatus::Checkmate(PieceColor::Black));
+        assert_eq!(status("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1"), GameStatus::Checkmate(PieceColor::White));
+    }
+
+    #[test]
+    fn stalemate() {
+        assert_eq!(status("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), GameStatus::Stalemate);
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut position = Position::from_fen(STARTING_FEN).unwrap();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        play(&mut position, &shuffle);
+        assert_eq!(position.status(), GameStatus::Ongoing);
+        play(&mut position, &shuffle);
+        assert_eq!(position.status(), GameStatus::Draw(DrawReason::Repetition));
+    }
+
+    #[test]
+    fn fifty_moves() {
+        assert_eq!(status("4k3/8/8/8/8/8/4P3/4K2R w K - 99 80"), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/4P3/4K2R w K - 100 80"), GameStatus::Draw(DrawReason::FiftyMoves));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        assert_eq!(status("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), GameStatus::Draw(DrawReason::InsufficientMaterial));
+        assert_eq!(status("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), GameStatus::Draw(DrawReason::InsufficientMaterial));
+        assert_eq!(status("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1"), GameStatus::Draw(DrawReason::InsufficientMaterial));
+        assert_eq!(status("3bk3/8/8/8/8/8/8/2B1K3 w - - 0 1"), GameStatus::Draw(DrawReason::InsufficientMaterial));
+        assert_eq!(status("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1"), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn checkmate_on_the_hundredth_halfmove() {
+        let mut position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+        play(&mut position, &["a1a8"]);
+        assert_eq!(position.halfmove_clock, 100);
+        assert_eq!(position.status(), GameStatus::Checkmate(PieceColor::White));
+    }
+}
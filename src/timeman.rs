@@ -0,0 +1,135 @@
+use std::time::Duration;
+use crate::Translation;
+use crate::search::Score;
+
+/// The time set aside from the clock for communication with the GUI, so that a move sent just
+/// before the deadline does not arrive after it.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// The number of moves the remaining time is divided between, when the time control does not say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// The times after which the search should end. The search stops once the `hard` deadline passes,
+/// even mid-iteration. Between iterations, it stops once a `soft` deadline passes, which the
+/// [TimeManager] adjusts by how settled the search appears.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Deadlines {
+    pub(crate) soft: Duration,
+    pub(crate) hard: Duration
+}
+
+impl Deadlines {
+    /// Deadlines for a search of exactly the given duration, as by `go movetime`.
+    pub(crate) fn fixed(duration: Duration) -> Self {
+        return Deadlines { soft: duration, hard: duration };
+    }
+}
+
+/// Divides the `remaining` time on the clock between the moves until the next time control, or
+/// [DEFAULT_MOVES_TO_GO] moves in a game without one. Most of the increment is spent, too, since it
+/// is regained after the move.
+///
+/// The soft deadline is the time the move is expected to take. The hard deadline allows for
+/// extension, but leaves most of the clock for the moves to come.
+pub(crate) fn allot(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Deadlines {
+    let available = remaining.saturating_sub(MOVE_OVERHEAD);
+    let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).clamp(1, DEFAULT_MOVES_TO_GO);
+    let share = available / moves_to_go + increment * 3 / 4;
+    let soft = share.min(available / 2);
+    let hard = (share * 3).min(available * 4 / 5).max(soft);
+    return Deadlines { soft, hard };
+}
+
+/// Decides, between iterations, whether the search should continue. While the best move keeps
+/// changing, or the score is falling, more time is spent. While one move takes nearly all of the
+/// effort, as happens when it is clearly best, less time is spent.
+pub(crate) struct TimeManager {
+    deadlines: Deadlines,
+    prior_best: Option<Translation>,
+    prior_score: Option<Score>,
+
+    /// Grows whenever the best move changes, and decays otherwise.
+    instability: f64
+}
+
+impl TimeManager {
+    pub(crate) fn new(deadlines: Deadlines) -> Self {
+        return TimeManager { deadlines, prior_best: None, prior_score: None, instability: 0.0 };
+    }
+
+    /// Considers the outcome of a completed iteration, and determines whether to begin another.
+    /// `effort` is the fraction of the iteration's root nodes which were spent on the best move.
+    pub(crate) fn should_stop(&mut self, elapsed: Duration, best: Option<Translation>, score: Score, effort: f64) -> bool {
+        if self.deadlines.soft >= self.deadlines.hard { return elapsed >= self.deadlines.hard; }
+
+        self.instability *= 0.5;
+        if self.prior_best.is_some() && best != self.prior_best { self.instability += 1.0; }
+        let stability_factor = 1.0 + self.instability * 0.6;
+
+        // A falling score suggests trouble, which deserves a closer look.
+        let drop = self.prior_score.map_or(0, |prior_score| prior_score - score);
+        let score_factor = if drop > 60 { 1.5 } else if drop > 25 { 1.2 } else { 1.0 };
+
+        let effort_factor = if effort > 0.9 { 0.5 } else if effort > 0.75 { 0.8 } else { 1.0 };
+
+        self.prior_best = best;
+        self.prior_score = Some(score);
+        let soft = self.deadlines.soft.mul_f64(stability_factor * score_factor * effort_factor);
+        return elapsed >= soft.min(self.deadlines.hard);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::timeman::allot;
+    use crate::timeman::Deadlines;
+    use crate::timeman::MOVE_OVERHEAD;
+    use crate::timeman::TimeManager;
+
+    fn ms(millis: u64) -> Duration { return Duration::from_millis(millis); }
+
+    #[test]
+    fn moves_to_go() {
+        // Sudden death divides the clock between thirty moves.
+        assert_eq!(allot(ms(60_030), ms(0), None).soft, ms(2_000));
+        assert_eq!(allot(ms(60_030), ms(0), Some(10)).soft, ms(6_000));
+        assert_eq!(allot(ms(60_030), ms(0), Some(10)).hard, ms(18_000));
+
+        // With one move to go, the clock is not spent entirely.
+        let last = allot(ms(60_030), ms(0), Some(1));
+        assert_eq!(last.soft, ms(30_000));
+        assert_eq!(last.hard, ms(48_000));
+    }
+
+    #[test]
+    fn increment() {
+        let without = allot(ms(60_030), ms(0), None);
+        let with = allot(ms(60_030), ms(2_000), None);
+        assert_eq!(with.soft - without.soft, ms(1_500));
+        assert!(with.hard > without.hard);
+    }
+
+    #[test]
+    fn movetime() {
+        let mut manager = TimeManager::new(Deadlines::fixed(ms(1_000)));
+        // However unsettled the search, a fixed time is spent in full, and no more.
+        assert!(!manager.should_stop(ms(900), None, 0, 1.0));
+        assert!(!manager.should_stop(ms(999), None, -500, 0.0));
+        assert!(manager.should_stop(ms(1_000), None, 0, 1.0));
+    }
+
+    #[test]
+    fn hard_limit_within_clock() {
+        for remaining in [0, 10, 30, 31, 100, 1_000, 60_000, 600_000] {
+            for increment in [0, 1_000, 10_000] {
+                for moves_to_go in [None, Some(1), Some(5), Some(40)] {
+                    let deadlines = allot(ms(remaining), ms(increment), moves_to_go);
+                    assert!(deadlines.soft <= deadlines.hard);
+                    assert!(deadlines.hard <= ms(remaining).saturating_sub(MOVE_OVERHEAD),
+                        "{} {} {:?}: {:?}", remaining, increment, moves_to_go, deadlines);
+                }
+            }
+        }
+    }
+}
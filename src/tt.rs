@@ -0,0 +1,304 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use crate::Translation;
+use crate::search::MATE;
+use crate::search::MAX_PLY;
+use crate::search::Score;
+use crate::zobrist::ZobristKey;
+
+/// Describes how a stored score relates to the true score of its position.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Bound {
+    /// The score is exact.
+    Exact = 0,
+
+    /// The search failed high. The true score is at least the stored score.
+    Lower = 1,
+
+    /// The search failed low. The true score is at most the stored score.
+    Upper = 2
+}
+
+/// What the search learned about a position, as recovered by [TranspositionTable::probe].
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct TtEntry {
+    pub(crate) best: Option<Translation>,
+    pub(crate) depth: u32,
+    pub(crate) bound: Bound,
+    pub(crate) score: Score
+}
+
+/// A slot of the table. The entry is packed into `data`, and `check` holds the position's
+/// [ZobristKey] XOR-ed with `data`. Threads read and write the two words without synchronizing, so
+/// a slot may be torn by concurrent writers. A torn slot fails verification, since `check ^ data`
+/// then no longer reproduces the key, and so it is simply treated as a miss.
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64
+}
+
+/// The number of slots in each bucket. A bucket of four sixteen-byte slots fills a cache line.
+const BUCKET_SIZE: usize = 4;
+
+/// Aligned so that each bucket lies within a single cache line.
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket { slots: [Slot; BUCKET_SIZE] }
+
+/// The number of distinct generations. See [TranspositionTable::new_search].
+const GENERATION_COUNT: u8 = 64;
+
+// The layout of the `data` word of a slot.
+const SCORE_SHIFT: u32 = 16;
+const DEPTH_SHIFT: u32 = 32;
+const BOUND_SHIFT: u32 = 40;
+const GENERATION_SHIFT: u32 = 42;
+
+/// Set in the `data` word of every stored entry, so that it is never mistaken for an empty slot.
+const OCCUPIED: u64 = 1 << 48;
+
+/// Records the scores and best moves of searched positions, so that they need not be searched again
+/// when reached by another order of moves, or by another thread, or in a later iteration.
+/// The table is shared between threads by reference, without locking. See [Slot].
+pub(crate) struct TranspositionTable {
+    buckets: Box<[Bucket]>,
+
+    /// Counts searches, modulo [GENERATION_COUNT]. Entries of past searches are replaced first.
+    generation: AtomicU8
+}
+
+/// Converts a score relative to the root into one relative to the position at `ply`. A mate score
+/// is stored as the distance to mate from the position itself, which holds wherever it is reached.
+fn score_to_tt(score: Score, ply: usize) -> Score {
+    if score >= MATE - MAX_PLY as Score { return score + ply as Score; }
+    if score <= -(MATE - MAX_PLY as Score) { return score - ply as Score; }
+    return score;
+}
+
+/// The inverse of [score_to_tt].
+fn score_from_tt(score: Score, ply: usize) -> Score {
+    if score >= MATE - MAX_PLY as Score { return score - ply as Score; }
+    if score <= -(MATE - MAX_PLY as Score) { return score + ply as Score; }
+    return score;
+}
+
+fn unpack_bound(data: u64) -> Bound {
+    return match (data >> BOUND_SHIFT) & 0b11 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper
+    }
+}
+
+fn unpack_depth(data: u64) -> u32 { return ((data >> DEPTH_SHIFT) & 0xFF) as u32; }
+
+fn unpack_generation(data: u64) -> u8 { return ((data >> GENERATION_SHIFT) & 0b111111) as u8; }
+
+/// A null [Translation], that of a1 onto itself, marks an entry without a best move.
+fn unpack_best(data: u64) -> Option<Translation> {
+    let translation = Translation { data: data as u16 };
+    if translation.data == 0 { return None; }
+    return Some(translation);
+}
+
+impl TranspositionTable {
+    /// Allocates an empty table occupying at most the given number of megabytes, and at least one bucket.
+    pub(crate) fn new(megabytes: usize) -> Self {
+        let count = (megabytes * 1024 * 1024 / size_of::<Bucket>()).max(1);
+        return TranspositionTable {
+            buckets: (0..count).map(|_| Bucket::default()).collect(),
+            generation: AtomicU8::new(0)
+        };
+    }
+
+    /// Forgets every entry.
+    pub(crate) fn clear(&self) {
+        for bucket in &self.buckets {
+            for slot in &bucket.slots {
+                slot.check.store(0, Ordering::Relaxed);
+                slot.data.store(0, Ordering::Relaxed);
+            }
+        }
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    /// Begins a new generation. Entries of older generations are replaced in preference to those
+    /// of the current one, regardless of their depth. This is called once per search.
+    pub(crate) fn new_search(&self) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        self.generation.store((generation + 1) % GENERATION_COUNT, Ordering::Relaxed);
+    }
+
+    fn locate(&self, key: ZobristKey) -> &Bucket {
+        return &self.buckets[(key % self.buckets.len() as u64) as usize];
+    }
+
+    /// Looks up the position with the given key, found at the given `ply` from the root.
+    pub(crate) fn probe(&self, key: ZobristKey, ply: usize) -> Option<TtEntry> {
+        for slot in &self.locate(key).slots {
+            let data = slot.data.load(Ordering::Relaxed);
+            if data & OCCUPIED == 0 || slot.check.load(Ordering::Relaxed) ^ data != key { continue; }
+            return Some(TtEntry {
+                best: unpack_best(data),
+                depth: unpack_depth(data),
+                bound: unpack_bound(data),
+                score: score_from_tt((data >> SCORE_SHIFT) as u16 as i16 as Score, ply)
+            });
+        }
+        return None;
+    }
+
+    /// Records what the search learned about the position with the given key, found at the given
+    /// `ply` from the root. An entry for the same position is overwritten, keeping its best move if
+    /// none is given. Otherwise, the entry of the bucket which is oldest and shallowest is replaced.
+    pub(crate) fn store(&self, key: ZobristKey, ply: usize, best: Option<Translation>, depth: u32, bound: Bound,
+                        score: Score) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let bucket = self.locate(key);
+
+        let mut victim = &bucket.slots[0];
+        let mut victim_worth = i32::MAX;
+        for slot in &bucket.slots {
+            let data = slot.data.load(Ordering::Relaxed);
+            if data & OCCUPIED > 0 && slot.check.load(Ordering::Relaxed) ^ data == key {
+                victim = slot;
+                break;
+            }
+            let age = (GENERATION_COUNT + generation - unpack_generation(data)) % GENERATION_COUNT;
+            let worth = match data & OCCUPIED {
+                0 => i32::MIN,
+                _ => unpack_depth(data) as i32 - 8 * age as i32
+            };
+            if worth < victim_worth {
+                victim = slot;
+                victim_worth = worth;
+            }
+        }
+
+        let best = best.or_else(|| {
+            let data = victim.data.load(Ordering::Relaxed);
+            if data & OCCUPIED == 0 || victim.check.load(Ordering::Relaxed) ^ data != key { return None; }
+            unpack_best(data)
+        });
+        let data = u64::from(best.map_or(0, |translation| translation.data))
+            | u64::from(score_to_tt(score, ply) as i16 as u16) << SCORE_SHIFT
+            | u64::from(depth.min(0xFF)) << DEPTH_SHIFT
+            | (bound as u64) << BOUND_SHIFT
+            | u64::from(generation) << GENERATION_SHIFT
+            | OCCUPIED;
+        victim.check.store(key ^ data, Ordering::Relaxed);
+        victim.data.store(data, Ordering::Relaxed);
+    }
+
+    /// Estimates the occupancy of the table, in permille, from the entries of the current generation
+    /// among the first thousand slots, as reported by the UCI `hashfull` field.
+    pub(crate) fn hashfull(&self) -> u32 {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let mut sampled = 0;
+        let mut full = 0;
+        for bucket in self.buckets.iter().take(1000 / BUCKET_SIZE) {
+            for slot in &bucket.slots {
+                let data = slot.data.load(Ordering::Relaxed);
+                sampled += 1;
+                if data & OCCUPIED > 0 && unpack_generation(data) == generation { full += 1; }
+            }
+        }
+        return full * 1000 / sampled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Translation;
+    use crate::search::MATE;
+    use crate::tt::Bound;
+    use crate::tt::Bucket;
+    use crate::tt::TranspositionTable;
+    use crate::zobrist::ZobristKey;
+
+    /// Produces the `i`th of a series of keys which all fall into the same bucket as `key`.
+    fn collide(tt: &TranspositionTable, key: ZobristKey, i: u64) -> ZobristKey {
+        return key + i * tt.buckets.len() as u64;
+    }
+
+    #[test]
+    fn bucket_fills_a_cache_line() {
+        assert_eq!(size_of::<Bucket>(), 64);
+        assert_eq!(align_of::<Bucket>(), 64);
+    }
+
+    #[test]
+    fn round_trip() {
+        let tt = TranspositionTable::new(1);
+        let key = 0x0123456789ABCDEF;
+        tt.store(key, 0, Some(Translation::new(12, 28)), 7, Bound::Lower, -42);
+        let entry = tt.probe(key, 0).unwrap();
+        assert_eq!(entry.best, Some(Translation::new(12, 28)));
+        assert_eq!(entry.depth, 7);
+        assert_eq!(entry.bound, Bound::Lower);
+        assert_eq!(entry.score, -42);
+
+        // Storing again without a best move keeps the one already known.
+        tt.store(key, 0, None, 8, Bound::Exact, 10);
+        assert_eq!(tt.probe(key, 0).unwrap().best, Some(Translation::new(12, 28)));
+    }
+
+    #[test]
+    fn mismatched_check_misses() {
+        let tt = TranspositionTable::new(1);
+        let key = 0x0123456789ABCDEF;
+        tt.store(key, 0, None, 3, Bound::Exact, 0);
+        assert!(tt.probe(collide(&tt, key, 1), 0).is_none());
+        assert!(tt.probe(key, 0).is_some());
+    }
+
+    #[test]
+    fn mate_scores_are_relative_to_ply() {
+        let tt = TranspositionTable::new(1);
+        // A mate found ten plies from the root, at a position three plies from the root.
+        tt.store(1, 3, None, 4, Bound::Exact, MATE - 10);
+        assert_eq!(tt.probe(1, 3).unwrap().score, MATE - 10);
+        assert_eq!(tt.probe(1, 5).unwrap().score, MATE - 12);
+        assert_eq!(tt.probe(1, 0).unwrap().score, MATE - 7);
+
+        tt.store(2, 3, None, 4, Bound::Exact, -(MATE - 10));
+        assert_eq!(tt.probe(2, 5).unwrap().score, -(MATE - 12));
+    }
+
+    #[test]
+    fn replacement() {
+        let tt = TranspositionTable::new(1);
+        let keys: Vec<ZobristKey> = (0..6).map(|i| collide(&tt, 99, i)).collect();
+        for (&key, depth) in keys.iter().zip([9, 2, 5, 7]) {
+            tt.store(key, 0, None, depth, Bound::Exact, 0);
+        }
+
+        // With the bucket full, the shallowest entry makes way.
+        tt.store(keys[4], 0, None, 3, Bound::Exact, 0);
+        assert!(tt.probe(keys[1], 0).is_none());
+        for key in [keys[0], keys[2], keys[3], keys[4]] { assert!(tt.probe(key, 0).is_some()); }
+
+        // An entry of a past search makes way before any of the current one, though it is deeper.
+        tt.new_search();
+        for (&key, depth) in [keys[2], keys[3], keys[4]].iter().zip([5, 7, 3]) {
+            tt.store(key, 0, None, depth, Bound::Exact, 0);
+        }
+        tt.store(keys[5], 0, None, 4, Bound::Exact, 0);
+        assert!(tt.probe(keys[0], 0).is_none());
+        for key in [keys[2], keys[3], keys[4], keys[5]] { assert!(tt.probe(key, 0).is_some()); }
+    }
+
+    #[test]
+    fn hashfull() {
+        let tt = TranspositionTable::new(1);
+        assert_eq!(tt.hashfull(), 0);
+        for key in 0..250 { tt.store(key, 0, None, 1, Bound::Exact, 0); }
+        assert_eq!(tt.hashfull(), 250);
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+        tt.clear();
+        assert_eq!(tt.hashfull(), 0);
+    }
+}
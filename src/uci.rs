@@ -0,0 +1,325 @@
+use std::io::BufRead;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use crate::PieceColor;
+use crate::Position;
+use crate::eval::evaluate;
+use crate::eval::trace;
+use crate::fen::STARTING_FEN;
+use crate::legal::generate_legal_moves;
+use crate::makemove::make_move;
+use crate::movegen::MoveList;
+use crate::notation::parse_coordinates;
+use crate::perft::divide;
+use crate::san::parse_san;
+use crate::san::to_san;
+use crate::search::is_mate_score;
+use crate::search::MATE;
+use crate::search::Score;
+use crate::search::search;
+use crate::search::SearchLimits;
+use crate::search::SearchOptions;
+use crate::search::SearchReport;
+use crate::timeman::allot;
+use crate::timeman::Deadlines;
+use crate::tt::TranspositionTable;
+
+/// The parameters of the `go` command. Durations are measured in milliseconds.
+#[derive(Copy, Clone, Default, Debug)]
+pub(crate) struct GoParameters {
+    pub(crate) depth: Option<u32>,
+    pub(crate) nodes: Option<u64>,
+    pub(crate) movetime: Option<u64>,
+    pub(crate) wtime: Option<u64>,
+    pub(crate) btime: Option<u64>,
+    pub(crate) winc: Option<u64>,
+    pub(crate) binc: Option<u64>,
+    pub(crate) movestogo: Option<u32>,
+    pub(crate) infinite: bool,
+
+    /// Not part of the protocol. Requests a [divide] of the current position, as Stockfish does.
+    pub(crate) perft: Option<u32>
+}
+
+fn parse_go(tokens: &[&str]) -> GoParameters {
+    let mut parameters = GoParameters::default();
+    let mut tokens = tokens.iter();
+    while let Some(&token) = tokens.next() {
+        let mut value = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+        match token {
+            "depth" => parameters.depth = value().map(|depth| depth as u32),
+            "nodes" => parameters.nodes = value(),
+            "movetime" => parameters.movetime = value(),
+            "wtime" => parameters.wtime = value(),
+            "btime" => parameters.btime = value(),
+            "winc" => parameters.winc = value(),
+            "binc" => parameters.binc = value(),
+            "movestogo" => parameters.movestogo = value().map(|moves| moves as u32),
+            "perft" => parameters.perft = value().map(|depth| depth as u32),
+            "infinite" => parameters.infinite = true,
+            _ => {}
+        }
+    }
+    return parameters;
+}
+
+/// Parses the arguments of the `position` command, which are either `startpos` or `fen` followed by
+/// a FEN record, and then optionally `moves` followed by moves in coordinate notation. A move which
+/// is not in coordinate notation is read as Standard Algebraic Notation, as a convenience to people
+/// typing commands by hand.
+fn parse_position(tokens: &[&str]) -> Result<Position, String> {
+    let moves_index = tokens.iter().position(|&token| token == "moves").unwrap_or(tokens.len());
+    let mut position = match tokens.first() {
+        Some(&"startpos") => Position::from_fen(STARTING_FEN).unwrap(),
+        Some(&"fen") => Position::from_fen(&tokens[1..moves_index].join(" "))
+            .map_err(|error| error.to_string())?,
+        _ => return Err(String::from("expected 'startpos' or 'fen'"))
+    };
+    for &text in tokens.iter().skip(moves_index + 1) {
+        let translation = parse_coordinates(&position, text)
+            .or_else(|| parse_san(&position, text))
+            .ok_or(format!("illegal move '{}'", text))?;
+        make_move(&mut position, translation);
+    }
+    return Ok(position);
+}
+
+/// A thread which is thinking about the current position, and which eventually reports `bestmove`.
+struct Worker {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>
+}
+
+/// The size of the transposition table, in megabytes, unless set by the `Hash` option.
+const DEFAULT_HASH_MEGABYTES: usize = 16;
+const MAX_HASH_MEGABYTES: usize = 65536;
+const MAX_THREADS: usize = 256;
+
+/// The names of the `check` options which switch features of the search on and off.
+/// See [lookup_switch].
+const SWITCHES: [&str; 8] = ["QuiescenceEvasions", "NullMovePruning", "LateMoveReductions", "FutilityPruning",
+    "ReverseFutilityPruning", "Razoring", "SingularExtensions", "CheckExtensions"];
+
+/// Finds the field of [SearchOptions] which the given `check` option sets, ignoring case, as the
+/// protocol requires of option names.
+fn lookup_switch<'a>(options: &'a mut SearchOptions, name: &str) -> Option<&'a mut bool> {
+    return match name.to_ascii_lowercase().as_str() {
+        "quiescenceevasions" => Some(&mut options.quiescence_evasions),
+        "nullmovepruning" => Some(&mut options.null_move_pruning),
+        "latemovereductions" => Some(&mut options.late_move_reductions),
+        "futilitypruning" => Some(&mut options.futility_pruning),
+        "reversefutilitypruning" => Some(&mut options.reverse_futility_pruning),
+        "razoring" => Some(&mut options.razoring),
+        "singularextensions" => Some(&mut options.singular_extensions),
+        "checkextensions" => Some(&mut options.check_extensions),
+        _ => None
+    }
+}
+
+/// The state of the engine between commands.
+struct Session {
+    position: Position,
+    worker: Option<Worker>,
+    tt: Arc<TranspositionTable>,
+    options: SearchOptions
+}
+
+impl Session {
+    /// Stops the worker, if there is one, and waits for it to report its move. A worker which
+    /// panicked never reported one, so a null move is reported in its place.
+    fn halt(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            worker.stop.store(true, Ordering::Relaxed);
+            if worker.handle.join().is_err() {
+                println!("info string the search failed");
+                println!("bestmove 0000");
+            }
+        }
+    }
+
+    fn go(&mut self, parameters: GoParameters) {
+        self.halt();
+        if let Some(depth) = parameters.perft {
+            let mut position = self.position.clone();
+            let counts = divide(&mut position, depth);
+            for (translation, count) in &counts { println!("{}: {}", translation, count); }
+            println!();
+            println!("Nodes searched: {}", counts.iter().map(|(_, count)| count).sum::<u64>());
+            return;
+        }
+
+        let position = self.position.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            let tt = Arc::clone(&self.tt);
+            let options = self.options;
+            std::thread::spawn(move || think(position, parameters, options, &tt, &stop))
+        };
+        self.worker = Some(Worker { stop, handle });
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) {
+        if name.eq_ignore_ascii_case("Hash") {
+            match value.parse::<usize>() {
+                Ok(megabytes) => self.tt = Arc::new(TranspositionTable::new(megabytes.clamp(1, MAX_HASH_MEGABYTES))),
+                Err(_) => println!("info string invalid value '{}' for option 'Hash'", value)
+            }
+            return;
+        }
+        if name.eq_ignore_ascii_case("Threads") {
+            match value.parse::<usize>() {
+                Ok(threads) => self.options.threads = threads.clamp(1, MAX_THREADS),
+                Err(_) => println!("info string invalid value '{}' for option 'Threads'", value)
+            }
+            return;
+        }
+        if let Some(switch) = lookup_switch(&mut self.options, name) {
+            match value.to_ascii_lowercase().as_str() {
+                "true" => *switch = true,
+                "false" => *switch = false,
+                _ => println!("info string invalid value '{}' for option '{}'", value, name)
+            }
+            return;
+        }
+        println!("info string unknown option '{}'", name);
+    }
+}
+
+/// Converts the parameters of the `go` command into [SearchLimits]. When playing on a clock, the
+/// time is allotted by [allot].
+fn limit(parameters: &GoParameters, ctm: PieceColor) -> SearchLimits {
+    let (remaining, increment) = match ctm {
+        PieceColor::White => (parameters.wtime, parameters.winc),
+        PieceColor::Black => (parameters.btime, parameters.binc)
+    };
+    let allotted = remaining.map(|remaining| allot(Duration::from_millis(remaining),
+        Duration::from_millis(increment.unwrap_or(0)), parameters.movestogo));
+    return SearchLimits {
+        depth: parameters.depth,
+        nodes: parameters.nodes,
+        time: match parameters.infinite {
+            true => None,
+            false => parameters.movetime.map(|movetime| Deadlines::fixed(Duration::from_millis(movetime)))
+                .or(allotted)
+        }
+    };
+}
+
+/// Formats a [Score] as the `score` field of an `info` line.
+fn format_score(score: Score) -> String {
+    if !is_mate_score(score) { return format!("cp {}", score); }
+    let plies = MATE - score.abs();
+    let moves = (plies + 1) / 2;
+    return format!("mate {}", if score > 0 { moves } else { -moves });
+}
+
+fn print_info(report: &SearchReport) {
+    let millis = report.elapsed.as_millis();
+    let nps = (report.nodes as u128 * 1000) / millis.max(1);
+    let pv: Vec<String> = report.pv.iter().map(|translation| translation.to_string()).collect();
+    println!("info depth {} score {} nodes {} nps {} hashfull {} time {} pv {}", report.depth,
+        format_score(report.score), report.nodes, nps, report.hashfull, millis, pv.join(" "));
+}
+
+/// Searches the given position, reporting each completed iteration, and finally the best move.
+fn think(mut position: Position, parameters: GoParameters, options: SearchOptions, tt: &TranspositionTable,
+         stop: &AtomicBool) {
+    let limits = limit(&parameters, position.ctm);
+    let report = search(&mut position, limits, options, tt, stop, print_info);
+
+    // The protocol forbids reporting the move of an infinite search before it is stopped.
+    while parameters.infinite && !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    match report.best {
+        Some(best) => println!("bestmove {}", best),
+        None => println!("bestmove 0000")
+    }
+}
+
+/// Splits a `setoption` command into the option's name and value.
+fn parse_setoption(tokens: &[&str]) -> (String, String) {
+    let value_index = tokens.iter().position(|&token| token == "value").unwrap_or(tokens.len());
+    let name_tokens = match tokens.first() {
+        Some(&"name") => &tokens[1..value_index],
+        _ => &tokens[..value_index]
+    };
+    let value_tokens = tokens.get(value_index + 1..).unwrap_or(&[]);
+    return (name_tokens.join(" "), value_tokens.join(" "));
+}
+
+/// Prints a description of the given position, in answer to the `d` command. This is not part of
+/// the protocol, but is a common extension for debugging.
+fn display(position: &Position) {
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &mut moves);
+    let moves: Vec<String> = moves.iter().map(|&translation| to_san(position, translation)).collect();
+    println!("Fen: {}", position.to_fen());
+    println!("Key: {:016X}", position.key);
+    println!("Status: {:?}", position.status());
+    println!("Legal moves: {}", moves.join(" "));
+}
+
+/// Speaks the [Universal Chess Interface](https://www.wbec-ridderkerk.nl/html/UCIProtocol.html)
+/// over standard input and output, until `quit` is received or the input ends.
+pub(crate) fn run() {
+    let mut session = Session {
+        position: Position::from_fen(STARTING_FEN).unwrap(),
+        worker: None,
+        tt: Arc::new(TranspositionTable::new(DEFAULT_HASH_MEGABYTES)),
+        options: SearchOptions::default()
+    };
+
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break; };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, arguments)) = tokens.split_first() else { continue; };
+        match command {
+            "uci" => {
+                println!("id name Virtual Chess Opponent {}", env!("CARGO_PKG_VERSION"));
+                println!("id author duncpro");
+                println!("option name Hash type spin default {} min 1 max {}", DEFAULT_HASH_MEGABYTES,
+                    MAX_HASH_MEGABYTES);
+                println!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS);
+                let mut defaults = SearchOptions::default();
+                for name in SWITCHES {
+                    println!("option name {} type check default {}", name, lookup_switch(&mut defaults, name).unwrap());
+                }
+                println!("uciok");
+            },
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                session.halt();
+                session.position = Position::from_fen(STARTING_FEN).unwrap();
+                session.tt.clear();
+            },
+            "position" => {
+                session.halt();
+                match parse_position(arguments) {
+                    Ok(position) => session.position = position,
+                    Err(message) => println!("info string {}", message)
+                }
+            },
+            "go" => session.go(parse_go(arguments)),
+            "stop" => session.halt(),
+            "setoption" => {
+                session.halt();
+                let (name, value) = parse_setoption(arguments);
+                session.set_option(&name, &value);
+            },
+            "eval" => match arguments.first() {
+                Some(&"trace") => println!("{}", trace(&session.position)),
+                _ => println!("Evaluation: {} (side to move's point of view)", evaluate(&session.position))
+            },
+            "d" => display(&session.position),
+            "quit" => break,
+            _ => println!("info string unknown command '{}'", command)
+        }
+    }
+    session.halt();
+}
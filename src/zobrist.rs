@@ -0,0 +1,148 @@
+use crate::bitboards;
+use crate::CastlingRights;
+use crate::Piece;
+use crate::PieceColor;
+use crate::Position;
+use crate::locate::RankwiseSquareOrdinal;
+
+/// A 64-bit digest of a [Position], formed by XOR-ing together a pseudorandom key for each of
+/// its features: every piece on its square, the color to move, each castling right held, and the
+/// file of the en-passant target. Equal positions have equal keys, and unequal positions almost
+/// certainly do not. Since XOR is its own inverse, the key is updated incrementally as features
+/// come and go.
+pub(crate) type ZobristKey = u64;
+
+/// The seed of the generator from which every key is drawn. Changing it invalidates every key
+/// recorded elsewhere, such as in an opening book.
+const SEED: u64 = 0x9E3779B97F4A7C15;
+
+/// Advances the SplitMix64 generator, producing its next state and output.
+const fn next_random(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return (state, z ^ (z >> 31));
+}
+
+const PIECE_KEYS_OFFSET: usize = 0;
+const PIECE_KEYS_COUNT: usize = 2 /* colors */ * 6 /* kinds */ * 64 /* squares */;
+const CTM_KEY_OFFSET: usize = PIECE_KEYS_OFFSET + PIECE_KEYS_COUNT;
+const CASTLING_KEYS_OFFSET: usize = CTM_KEY_OFFSET + 1;
+const CASTLING_KEYS_COUNT: usize = 4 /* rights */;
+const EP_KEYS_OFFSET: usize = CASTLING_KEYS_OFFSET + CASTLING_KEYS_COUNT;
+const EP_KEYS_COUNT: usize = 8 /* files */;
+const ZOBRIST_TABLE_SIZE: usize = EP_KEYS_OFFSET + EP_KEYS_COUNT;
+
+const fn compile_zobrist_table() -> [ZobristKey; ZOBRIST_TABLE_SIZE] {
+    let mut table: [ZobristKey; ZOBRIST_TABLE_SIZE] = [0; ZOBRIST_TABLE_SIZE];
+    let mut state = SEED;
+    let mut i = 0;
+    loop {
+        if i >= ZOBRIST_TABLE_SIZE { break; }
+        let (next_state, key) = next_random(state);
+        state = next_state;
+        table[i] = key;
+        i += 1;
+    }
+    return table;
+}
+
+static ZOBRIST_TABLE: [ZobristKey; ZOBRIST_TABLE_SIZE] = compile_zobrist_table();
+
+pub(crate) fn lookup_piece_key(piece: Piece, sordinal: RankwiseSquareOrdinal) -> ZobristKey {
+    let i = (piece.color as usize * 6 + piece.kind as usize) * 64 + sordinal;
+    return ZOBRIST_TABLE[PIECE_KEYS_OFFSET + i];
+}
+
+/// Produces the key which is present while Black is to move.
+pub(crate) fn lookup_ctm_key() -> ZobristKey {
+    return ZOBRIST_TABLE[CTM_KEY_OFFSET];
+}
+
+/// Produces the combined key of every right held in the given [CastlingRights].
+pub(crate) fn lookup_castling_key(castling: CastlingRights) -> ZobristKey {
+    let mut key: ZobristKey = 0;
+    bitboards::scan(u64::from(castling), |right| {
+        key ^= ZOBRIST_TABLE[CASTLING_KEYS_OFFSET + right as usize];
+    });
+    return key;
+}
+
+/// Produces the key of the given en-passant target, which depends only upon its file. A target is
+/// only ever set when a pawn could capture onto it, so positions which differ in nothing else that
+/// matters to the rules of repetition share a key.
+pub(crate) fn lookup_ep_key(ep_target: Option<RankwiseSquareOrdinal>) -> ZobristKey {
+    return match ep_target {
+        Some(sordinal) => ZOBRIST_TABLE[EP_KEYS_OFFSET + sordinal % 8],
+        None => 0
+    }
+}
+
+/// Computes the [ZobristKey] of the given [Position] from scratch. Otherwise, the key is maintained
+/// incrementally, as pieces are placed and removed and as moves are made and unmade.
+pub(crate) fn compute_key(position: &Position) -> ZobristKey {
+    let mut key: ZobristKey = 0;
+    for (sordinal, square) in position.squares.iter().enumerate() {
+        if let Some(piece) = square.occupant { key ^= lookup_piece_key(piece, sordinal); }
+    }
+    if position.ctm == PieceColor::Black { key ^= lookup_ctm_key(); }
+    key ^= lookup_castling_key(position.castling);
+    key ^= lookup_ep_key(position.ep_target);
+    return key;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Position;
+    use crate::fen::STARTING_FEN;
+    use crate::legal::generate_legal_moves;
+    use crate::makemove::make_move;
+    use crate::makemove::unmake_move;
+    use crate::movegen::MoveList;
+    use crate::notation::parse_coordinates;
+    use crate::zobrist::compute_key;
+
+    /// Checks the incrementally updated key against [compute_key] throughout the move tree.
+    fn verify(position: &mut Position, depth: u32) {
+        assert_eq!(position.key, compute_key(position), "{}", position.to_fen());
+        if depth == 0 { return; }
+        let mut moves = MoveList::new();
+        generate_legal_moves(position, &mut moves);
+        for translation in moves {
+            let undo = make_move(position, translation);
+            verify(position, depth - 1);
+            unmake_move(position, undo);
+        }
+    }
+
+    #[test]
+    fn incremental_keys() {
+        for record in [STARTING_FEN, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                       "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                       "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"] {
+            verify(&mut Position::from_fen(record).unwrap(), 3);
+        }
+    }
+
+    #[test]
+    fn en_passant_key_requires_capturer() {
+        // No black pawn can capture the advanced pawn, so the target does not distinguish the positions.
+        let mut position = Position::from_fen(STARTING_FEN).unwrap();
+        let translation = parse_coordinates(&position, "e2e4").unwrap();
+        make_move(&mut position, translation);
+        assert_eq!(position.ep_target, None);
+        let reached = Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(position.key, reached.key);
+        let recorded = Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(recorded.ep_target, None);
+        assert_eq!(position.key, recorded.key);
+
+        // Here a black pawn can capture, so the target is kept.
+        let mut position = Position::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        let translation = parse_coordinates(&position, "e2e4").unwrap();
+        make_move(&mut position, translation);
+        assert!(position.ep_target.is_some());
+        assert_eq!(position.key, compute_key(&position));
+    }
+}
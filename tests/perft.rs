@@ -0,0 +1,130 @@
+//! Runs the `perft` command of the binary against the reference positions of the
+//! [Chess Programming Wiki](https://www.chessprogramming.org/Perft_Results), and asserts the
+//! published node counts.
+//!
+//! The deeper tier is ignored by default. Run it with `cargo test --release -- --ignored`.
+
+#![allow(clippy::needless_return)]
+
+use std::process::Command;
+
+const STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_4_MIRRORED: &str = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+/// Runs `perft <depth> <fen>` and extracts the total node count from its output.
+fn perft(fen: &str, depth: u32) -> u64 {
+    let output = Command::new(env!("CARGO_BIN_EXE_virtual-chess-opponent"))
+        .arg("perft")
+        .arg(depth.to_string())
+        .arg(fen)
+        .output()
+        .expect("failed to run the binary");
+    assert!(output.status.success(), "perft exited with {}", output.status);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    return stdout.lines()
+        .find_map(|line| line.strip_prefix("Nodes searched: "))
+        .expect("perft did not report a node count")
+        .parse()
+        .unwrap();
+}
+
+fn assert_perft(fen: &str, expected: &[u64]) {
+    for (i, &nodes) in expected.iter().enumerate() {
+        let depth = i as u32 + 1;
+        assert_eq!(perft(fen, depth), nodes, "perft({}) of {}", depth, fen);
+    }
+}
+
+#[test]
+fn starting_position() {
+    assert_perft(STARTING_POSITION, &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn kiwipete() {
+    assert_perft(KIWIPETE, &[48, 2039, 97862]);
+}
+
+#[test]
+fn position_3() {
+    assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624]);
+}
+
+#[test]
+fn position_4() {
+    assert_perft(POSITION_4, &[6, 264, 9467, 422333]);
+}
+
+#[test]
+fn position_4_mirrored() {
+    assert_perft(POSITION_4_MIRRORED, &[6, 264, 9467, 422333]);
+}
+
+#[test]
+fn position_5() {
+    assert_perft(POSITION_5, &[44, 1486, 62379]);
+}
+
+#[test]
+fn position_6() {
+    assert_perft(POSITION_6, &[46, 2079, 89890]);
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let output = Command::new(env!("CARGO_BIN_EXE_virtual-chess-opponent"))
+        .args(["perft", "divide", "3", KIWIPETE])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let counts: Vec<(&str, u64)> = stdout.lines()
+        .filter_map(|line| line.split_once(": "))
+        .filter(|(mv, _)| mv.len() <= 5 && mv.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|(mv, count)| (mv, count.parse().unwrap()))
+        .collect();
+    assert_eq!(counts.len(), 48);
+    assert!(counts.contains(&("e1g1", 2059)));
+    assert!(counts.contains(&("e1c1", 1887)));
+    assert_eq!(counts.iter().map(|(_, count)| count).sum::<u64>(), 97862);
+}
+
+#[test]
+#[ignore]
+fn starting_position_deep() {
+    assert_eq!(perft(STARTING_POSITION, 6), 119060324);
+}
+
+#[test]
+#[ignore]
+fn kiwipete_deep() {
+    assert_eq!(perft(KIWIPETE, 5), 193690690);
+}
+
+#[test]
+#[ignore]
+fn position_3_deep() {
+    assert_eq!(perft(POSITION_3, 7), 178633661);
+}
+
+#[test]
+#[ignore]
+fn position_4_deep() {
+    assert_eq!(perft(POSITION_4, 5), 15833292);
+}
+
+#[test]
+#[ignore]
+fn position_5_deep() {
+    assert_eq!(perft(POSITION_5, 5), 89941194);
+}
+
+#[test]
+#[ignore]
+fn position_6_deep() {
+    assert_eq!(perft(POSITION_6, 5), 164075551);
+}